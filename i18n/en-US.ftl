@@ -27,4 +27,116 @@ branch-has-not-been-deleted = { $branch } has not been deleted.
 no-valid-branch-found = No valid branch found. Is the target folder a valid Git repository?
 not-a-git-repository = Not a Git repository.
 git-not-found = Git cannot be found. Please install it.
-protected = (protected)
\ No newline at end of file
+protected = (protected)
+squash-merged = (squash-merged)
+rebase-merged =
+    { $count ->
+        [one] (rebase-merged, { $count } commit matched)
+        *[other] (rebase-merged, { $count } commits matched)
+    }
+merged-into = (merged into { $targets })
+no-such-target = Target branch not found: { $targets }. Start a target with ^ to match branch names with a regular expression.
+invalid-target-pattern = Invalid regular expression { $pattern } in the target branches.
+no-upstream = { $branch } has no upstream branch, using the local branch.
+target-behind-upstream =
+    { $count ->
+        [one] Warning: { $branch } is { $count } commit behind { $upstream }. Branches merged since the last pull will not be found, use --upstream to check against { $upstream }.
+        *[other] Warning: { $branch } is { $count } commits behind { $upstream }. Branches merged since the last pull will not be found, use --upstream to check against { $upstream }.
+    }
+using-default-branch = Using default branch { $branch }, resolved from { $source }.
+no-default-branch-found = No default branch found. Is a remote HEAD or init.defaultBranch configured?
+found-gone-protected =
+    { $count ->
+        [one] Found { $count } protected branch whose upstream is gone:
+        *[other] Found { $count } protected branches whose upstream is gone:
+    }
+found-gone =
+    { $count ->
+        [one] Found { $count } branch whose upstream is gone:
+        *[other] Found { $count } branches whose upstream is gone:
+    }
+no-gone-branch = No branches whose upstream is gone found.
+upstream-gone = (upstream gone)
+fetched-remote =
+    { $count ->
+        [0] Fetched { $remote }.
+        [one] Fetched { $remote }, pruned { $count } remote-tracking branch:
+        *[other] Fetched { $remote }, pruned { $count } remote-tracking branches:
+    }
+fetch-failed = Warning: { $remote } cannot be fetched, using the current remote-tracking branches.
+found-merged-remote-protected =
+    { $count ->
+        [one] Found { $count } merged but protected remote branch on { $branch }:
+        *[other] Found { $count } merged but protected remote branches on { $branch }:
+    }
+found-merged-remote =
+    { $count ->
+        [one] Found { $count } merged remote branch on { $branch }:
+        *[other] Found { $count } merged remote branches on { $branch }:
+    }
+no-merged-remote-branch = No merged branches of { $remote } found on { $branch }.
+no-such-remote = No remote named { $remote }.
+remote-deletion-warning = /!\ WARNING: deleted branches will be removed from { $remote } for everyone using it. This cannot be undone from your local repository. /!\
+delete-remote-branch-yes-no = Delete remote branch { $branch } for everyone? /!\ [y]es, [n]o:
+found-stale-protected =
+    { $count ->
+        [one] Found { $count } protected stale remote-tracking branch:
+        *[other] Found { $count } protected stale remote-tracking branches:
+    }
+found-stale =
+    { $count ->
+        [one] Found { $count } stale remote-tracking branch:
+        *[other] Found { $count } stale remote-tracking branches:
+    }
+no-stale-ref = No stale remote-tracking branches found.
+remote-removed = (remote removed)
+not-on-remote = (not on remote)
+ls-remote-failed = Warning: { $remote } cannot be reached, its remote-tracking branches are not checked.
+checked-out-in-worktree = (checked out in { $worktree })
+worktree-not-clean = { $branch } is checked out in worktree { $worktree }, which has local changes.
+remove-worktree-yes-no = { $branch } is checked out in worktree { $worktree }. Remove this worktree? [y]es, [n]o:
+worktree-removed = Worktree { $worktree } removed.
+invalid-protected-pattern = Invalid regular expression { $pattern } in { $key }, please fix it before cleaning up branches.
+config-cannot-be-read = Git configuration cannot be read, protected branches are unknown: { $error }
+protection-rules =
+    { $count ->
+        [one] { $count } rule in { $key }:
+        *[other] { $count } rules in { $key }:
+    }
+no-protection-rule = No rule in { $key }.
+rule-origin = (from { $origin })
+unsupported-by-backend = This operation is not supported by the selected backend.
+not-selected = (not selected)
+no-branch-selected = No branch selected, no branch deleted.
+stdin-not-a-terminal = Cannot ask which branches to delete, as the standard input is not a terminal. Use --yes to delete them without asking, or --dry-run to only list them.
+unmerged-commits =
+    { $count ->
+        [one] { $branch } has { $count } commit on none of the target branches, which would be lost:
+        *[other] { $branch } has { $count } commits on none of the target branches, which would be lost:
+    }
+force-delete-type-name = Type { $branch } to force delete it anyway, or press Enter to keep it:
+branch-archived = Its tip is archived as { $reference }.
+no-archived-branch = No archived branches.
+archived-branches =
+    { $count ->
+        [one] { $count } archived branch:
+        *[other] { $count } archived branches:
+    }
+archive-purged =
+    { $count ->
+        [one] Purged { $count } branch archived more than { $days } days ago:
+        *[other] Purged { $count } branches archived more than { $days } days ago:
+    }
+no-archive-purged = No branch archived more than { $days } days ago.
+not-in-journal = No deletion of { $branch } to restore found in the journal.
+nothing-to-undo = No deletion to undo found in the journal.
+branch-already-exists = A branch named { $branch } already exists.
+commit-not-found = { $branch } cannot be restored, as its commit { $tip } no longer exists.
+branch-restored = Branch { $branch } restored at { $tip }.
+upstream-restored = Its upstream is { $merge } of { $remote } again.
+bundle-failed = The branches cannot be saved to { $path }, no branch was deleted: { $error }
+branches-bundled =
+    { $count ->
+        [one] Saved { $count } branch to { $path }:
+        *[other] Saved { $count } branches to { $path }:
+    }
@@ -0,0 +1,141 @@
+/*
+Git Broom
+Copyright (C) 2024  All contributors.
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! The archive of deleted branches: before a branch is deleted, its tip is recorded under
+//! `refs/broom/archive/<date>/<id>/<ref>`, so that the deletion can be reverted until the archive
+//! is purged. `<id>` is unique to each deletion, and `<ref>` is the full name of the branch without
+//! `refs/`, such as `heads/feature` or `remotes/origin/feature`, so that archive references never
+//! collide.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The namespace of the archive references.
+pub(crate) const ARCHIVE_PREFIX: &str = "refs/broom/archive/";
+
+/// A deleted branch whose tip is kept in the archive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArchivedBranch {
+    /// The short name the branch had, such as `feature` or `origin/feature`.
+    pub name: String,
+    /// Whether the branch was a remote or remote-tracking branch, rather than a local branch.
+    pub remote: bool,
+    /// The day the branch was deleted, as `YYYY-MM-DD` in UTC.
+    pub date: String,
+    /// The full name of the archive reference, such as
+    /// `refs/broom/archive/2024-05-01/1714521600000000000-4242/heads/feature`.
+    pub reference: String,
+    /// The id of the commit the branch pointed to.
+    pub tip: String,
+}
+
+impl ArchivedBranch {
+    /// Reads an archive reference, `None` if its name does not hold a valid date and the name of
+    /// a branch.
+    pub(crate) fn from_reference(reference: String, tip: String) -> Option<Self> {
+        let (date, archived) = reference.strip_prefix(ARCHIVE_PREFIX)?.split_once('/')?;
+        let (_, archived) = archived.split_once('/')?;
+        parse_date(date)?;
+
+        let (name, remote) = match archived.strip_prefix("heads/") {
+            Some(name) => (name, false),
+            None => (archived.strip_prefix("remotes/")?, true),
+        };
+
+        Some(Self {
+            name: name.to_string(),
+            remote,
+            date: date.to_string(),
+            reference,
+            tip,
+        })
+    }
+
+    /// The number of whole days since the branch was archived.
+    pub fn age_in_days(&self) -> u64 {
+        let archived = parse_date(&self.date).unwrap_or_default();
+
+        today().saturating_sub(archived).max(0) as u64
+    }
+}
+
+/// Returns the name of the archive reference of a branch deleted today, given its full name and the
+/// identifier of the deletion.
+pub(crate) fn archive_reference(ref_name: &str, id: &str) -> String {
+    format!(
+        "{}{}/{}/{}",
+        ARCHIVE_PREFIX,
+        format_date(today()),
+        id,
+        ref_name.strip_prefix("refs/").unwrap_or(ref_name)
+    )
+}
+
+/// The number of days since the Unix epoch, in UTC.
+fn today() -> i64 {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+
+    (seconds / 86_400) as i64
+}
+
+/// Formats a number of days since the Unix epoch as `YYYY-MM-DD`, in the proleptic Gregorian
+/// calendar.
+fn format_date(days: i64) -> String {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Parses a `YYYY-MM-DD` date into a number of days since the Unix epoch, `None` if it is not a
+/// valid date.
+fn parse_date(date: &str) -> Option<i64> {
+    let mut parts = date.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let shifted_month = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * shifted_month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    // Rejects days past the end of the month, such as 2023-02-30.
+    (format_date(days) == date).then_some(days)
+}
@@ -0,0 +1,175 @@
+/*
+Git Broom
+Copyright (C) 2024  All contributors.
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! The access to the repository, through which every Git operation goes.
+//!
+//! [`CommandBackend`] runs the `git` command, and [`GixBackend`] works in-process with gitoxide,
+//! without needing Git to be installed. Another implementation, such as a fake repository for
+//! tests, can be given to [`crate::GitBroomBuilder::backend`].
+
+use std::path::{Path, PathBuf};
+
+use crate::branch::Commit;
+use crate::error::Error;
+
+mod command;
+mod gitoxide;
+
+pub use command::CommandBackend;
+pub use gitoxide::GixBackend;
+
+/// A local branch, along with its upstream branch.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LocalBranch {
+    /// The short name of the branch, such as `feature`.
+    pub name: String,
+    /// The short name of the upstream branch, such as `origin/feature`, if one is configured.
+    pub upstream: Option<String>,
+    /// The remote of the upstream branch, if one is configured.
+    pub upstream_remote: Option<String>,
+    /// The upstream branch is configured, but no longer exists.
+    pub upstream_gone: bool,
+}
+
+/// The Git operations Git Broom relies on.
+///
+/// Revisions are given as Git understands them, such as `main`, `origin/main` or
+/// `refs/heads/main`. The operations which reach beyond the repository, or which inspect the
+/// content of commits, fail with [`Error::Unsupported`] by default.
+pub trait Backend: Send + Sync {
+    /// Returns the Git directory of the current worktree and the common Git directory of the
+    /// repository, as absolute paths. Fails with [`Error::NotARepository`] if there is no
+    /// repository.
+    fn git_directories(&self) -> Result<(PathBuf, PathBuf), Error>;
+
+    /// Returns the full name of the branch checked out in the current worktree, `None` if `HEAD`
+    /// is detached.
+    fn head(&self) -> Result<Option<String>, Error>;
+
+    fn remotes(&self) -> Result<Vec<String>, Error>;
+
+    fn local_branches(&self) -> Result<Vec<LocalBranch>, Error>;
+
+    /// Returns the full names of the remote-tracking branches, such as `refs/remotes/origin/main`,
+    /// leaving out symbolic references such as `refs/remotes/origin/HEAD`.
+    fn remote_tracking_branches(&self) -> Result<Vec<String>, Error>;
+
+    /// Returns the full name of the reference a symbolic reference points to, `None` if it does
+    /// not exist or is not symbolic.
+    fn symbolic_ref_target(&self, name: &str) -> Result<Option<String>, Error>;
+
+    /// Returns the id of the commit a revision resolves to, `None` if it does not resolve to one.
+    fn resolve_commit(&self, revision: &str) -> Result<Option<String>, Error>;
+
+    fn is_ancestor(&self, ancestor: &str, descendant: &str) -> Result<bool, Error>;
+
+    /// Returns the short names of the local branches merged on a revision.
+    fn merged_branches(&self, target: &str) -> Result<Vec<String>, Error> {
+        let mut branches: Vec<String> = Vec::new();
+
+        for branch in self.local_branches()? {
+            if self.is_ancestor(&format!("refs/heads/{}", branch.name), target)? {
+                branches.push(branch.name);
+            }
+        }
+
+        Ok(branches)
+    }
+
+    /// Counts the commits reachable from `to` and not from `from`.
+    fn count_commits(&self, from: &str, to: &str) -> Result<usize, Error>;
+
+    /// Returns the worktrees of the repository, the main one included, along with the short name
+    /// of the branch checked out in each of them.
+    fn worktrees(&self) -> Result<Vec<(PathBuf, String)>, Error>;
+
+    /// Deletes a local branch. Unless forced, a branch which is not merged on its upstream or on
+    /// `HEAD` is kept, like `git branch -d` does.
+    ///
+    /// Fails with [`Error::Deletion`] when the branch cannot be deleted, telling why.
+    fn delete_branch(&self, name: &str, force: bool) -> Result<(), Error>;
+
+    /// Deletes a remote-tracking branch, given by its short name such as `origin/feature`.
+    fn delete_remote_tracking_branch(&self, name: &str) -> Result<(), Error>;
+
+    /// Configures the upstream of a local branch in the local configuration, as
+    /// `branch.<name>.remote` and `branch.<name>.merge`.
+    fn set_upstream(&self, name: &str, remote: &str, merge: &str) -> Result<(), Error>;
+
+    /// Returns the full names of the references starting with `prefix`, such as
+    /// `refs/broom/archive/`, along with the id of the object each one points to.
+    fn references(&self, prefix: &str) -> Result<Vec<(String, String)>, Error>;
+
+    /// Creates a reference pointing to a commit, or moves it if it exists.
+    fn set_reference(&self, name: &str, id: &str) -> Result<(), Error>;
+
+    /// Deletes a reference given by its full name, which is not a branch.
+    fn delete_reference(&self, name: &str) -> Result<(), Error>;
+
+    /// Fetches a remote with pruning, returning whether it succeeded.
+    fn fetch(&self, _remote: &str) -> Result<bool, Error> {
+        Err(Error::Unsupported)
+    }
+
+    /// Returns the names of the branches of a remote, `None` if it cannot be reached.
+    fn remote_heads(&self, _remote: &str) -> Result<Option<Vec<String>>, Error> {
+        Err(Error::Unsupported)
+    }
+
+    /// Deletes a branch from a remote, refusing to if it was updated on the remote since it was
+    /// last fetched.
+    fn delete_remote_branch(&self, _remote: &str, _branch: &str) -> Result<(), Error> {
+        Err(Error::Unsupported)
+    }
+
+    /// Returns the number of commits unique to `branch` if all of them have a patch-equivalent
+    /// commit on `target`, following `git cherry` semantics.
+    fn rebase_merged_commits(&self, _target: &str, _branch: &str) -> Result<Option<usize>, Error> {
+        Err(Error::Unsupported)
+    }
+
+    /// Checks whether the changes of `branch` since its merge base with `target` were applied on
+    /// `target` as a single commit.
+    fn is_squash_merged(&self, _target: &str, _branch: &str) -> Result<bool, Error> {
+        Err(Error::Unsupported)
+    }
+
+    /// Returns the commits reachable from `branch` and from none of `targets`, newest first.
+    fn unmerged_commits(&self, _branch: &str, _targets: &[String]) -> Result<Vec<Commit>, Error> {
+        Err(Error::Unsupported)
+    }
+
+    /// Writes references, given by their full names, to a bundle file along with their history.
+    fn create_bundle(&self, _path: &Path, _references: &[String]) -> Result<(), Error> {
+        Err(Error::Unsupported)
+    }
+
+    /// Checks that a bundle file is valid, and that the commits it needs are in the repository.
+    fn verify_bundle(&self, _path: &Path) -> Result<(), Error> {
+        Err(Error::Unsupported)
+    }
+
+    /// Checks that a worktree has no local changes, so that removing it loses nothing.
+    fn is_worktree_clean(&self, _worktree: &Path) -> Result<bool, Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn remove_worktree(&self, _worktree: &Path) -> Result<(), Error> {
+        Err(Error::Unsupported)
+    }
+}
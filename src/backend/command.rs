@@ -0,0 +1,442 @@
+/*
+Git Broom
+Copyright (C) 2024  All contributors.
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::{Backend, LocalBranch};
+use crate::branch::Commit;
+use crate::error::{DeletionError, DeletionErrorKind, Error};
+
+/// Runs the `git` command, which must be installed.
+#[derive(Clone, Debug, Default)]
+pub struct CommandBackend {
+    /// Path of the Git repository, the current path if `None`.
+    repository: Option<PathBuf>,
+}
+
+impl CommandBackend {
+    pub fn new(repository: Option<PathBuf>) -> Self {
+        Self { repository }
+    }
+
+    /// Creates a Git command running against the repository, without changing the working
+    /// directory of the process.
+    fn git(&self) -> Command {
+        let mut command = Command::new("git");
+
+        if let Some(repository) = &self.repository {
+            command.current_dir(repository);
+        }
+
+        command
+    }
+
+    /// Runs a Git command and returns its trimmed standard output, or `None` if the command failed
+    /// or printed nothing.
+    fn run_git_for_output(&self, args: &[&str]) -> Result<Option<String>, Error> {
+        let output = self.git().args(args).output()?;
+
+        if !output.status.success() {
+            return Ok(None);
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+        if stdout.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(stdout))
+        }
+    }
+
+    /// Runs a Git command and returns the lines of its standard output, empty ones left out.
+    fn run_git_for_lines(&self, args: &[&str]) -> Result<Vec<String>, Error> {
+        let output = self.git().args(args).output()?;
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect())
+    }
+
+    fn run_git_for_status(&self, args: &[&str]) -> Result<bool, Error> {
+        Ok(self.git().args(args).output()?.status.success())
+    }
+
+    /// Runs a Git command updating the repository, failing with the diagnostic of Git if it does
+    /// not succeed.
+    fn run_git_for_update<I, S>(&self, args: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let output = self.git().args(args).output()?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(Error::backend(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ))
+        }
+    }
+
+    /// Runs a Git command deleting something, failing with the diagnostic of Git if it refuses.
+    ///
+    /// Git runs with the C locale, so that the reason of a refusal can be told from its
+    /// diagnostic.
+    fn run_git_for_deletion<I, S>(&self, args: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let output = self.git().args(args).env("LC_ALL", "C").output()?;
+
+        if output.status.success() {
+            return Ok(());
+        }
+
+        let message = String::from_utf8_lossy(&output.stderr)
+            .lines()
+            .filter(|line| !line.starts_with("hint:") && !line.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Err(DeletionError::new(deletion_error_kind(&message), message).into())
+    }
+}
+
+impl Backend for CommandBackend {
+    fn git_directories(&self) -> Result<(PathBuf, PathBuf), Error> {
+        if Command::new("git").arg("--version").output().is_err() {
+            return Err(Error::GitNotFound);
+        }
+
+        let directories = self
+            .run_git_for_output(&[
+                "rev-parse",
+                "--path-format=absolute",
+                "--git-dir",
+                "--git-common-dir",
+            ])
+            .map_err(|_| Error::NotARepository)?;
+
+        match directories
+            .as_deref()
+            .and_then(|directories| directories.split_once('\n'))
+        {
+            Some((git_dir, common_dir)) => Ok((PathBuf::from(git_dir), PathBuf::from(common_dir))),
+            None => Err(Error::NotARepository),
+        }
+    }
+
+    fn head(&self) -> Result<Option<String>, Error> {
+        self.run_git_for_output(&["symbolic-ref", "--quiet", "HEAD"])
+    }
+
+    fn remotes(&self) -> Result<Vec<String>, Error> {
+        self.run_git_for_lines(&["remote"])
+    }
+
+    fn local_branches(&self) -> Result<Vec<LocalBranch>, Error> {
+        let lines = self.run_git_for_lines(&[
+            "for-each-ref",
+            "--format=%(refname:short)%00%(upstream:short)%00%(upstream:remotename)%00%(upstream:track)",
+            "refs/heads",
+        ])?;
+
+        Ok(lines
+            .iter()
+            .map(|line| {
+                let mut fields = line
+                    .split('\0')
+                    .map(|field| Some(field.to_string()).filter(|field| !field.is_empty()));
+
+                LocalBranch {
+                    name: fields.next().flatten().unwrap_or_default(),
+                    upstream: fields.next().flatten(),
+                    upstream_remote: fields.next().flatten(),
+                    upstream_gone: fields.next().flatten().as_deref() == Some("[gone]"),
+                }
+            })
+            .collect())
+    }
+
+    fn remote_tracking_branches(&self) -> Result<Vec<String>, Error> {
+        let lines = self.run_git_for_lines(&[
+            "for-each-ref",
+            "--format=%(refname)%00%(symref)",
+            "refs/remotes",
+        ])?;
+
+        Ok(lines
+            .iter()
+            .filter_map(|line| line.split_once('\0'))
+            .filter(|(_, symref)| symref.is_empty())
+            .map(|(name, _)| name.to_string())
+            .collect())
+    }
+
+    fn symbolic_ref_target(&self, name: &str) -> Result<Option<String>, Error> {
+        self.run_git_for_output(&["symbolic-ref", "--quiet", name])
+    }
+
+    fn resolve_commit(&self, revision: &str) -> Result<Option<String>, Error> {
+        self.run_git_for_output(&[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{}^{{commit}}", revision),
+        ])
+    }
+
+    fn is_ancestor(&self, ancestor: &str, descendant: &str) -> Result<bool, Error> {
+        self.run_git_for_status(&["merge-base", "--is-ancestor", ancestor, descendant])
+    }
+
+    fn merged_branches(&self, target: &str) -> Result<Vec<String>, Error> {
+        self.run_git_for_lines(&["branch", "--merged", target, "--format=%(refname:short)"])
+    }
+
+    fn count_commits(&self, from: &str, to: &str) -> Result<usize, Error> {
+        Ok(self
+            .run_git_for_output(&["rev-list", "--count", &format!("{}..{}", from, to)])?
+            .and_then(|count| count.parse::<usize>().ok())
+            .unwrap_or(0))
+    }
+
+    fn worktrees(&self) -> Result<Vec<(PathBuf, String)>, Error> {
+        let mut worktrees: Vec<(PathBuf, String)> = Vec::new();
+        let mut path: Option<PathBuf> = None;
+
+        for line in self.run_git_for_lines(&["worktree", "list", "--porcelain"])? {
+            if let Some(worktree) = line.strip_prefix("worktree ") {
+                path = Some(PathBuf::from(worktree));
+            } else if let Some(branch) = line.strip_prefix("branch refs/heads/") {
+                if let Some(path) = path.take() {
+                    worktrees.push((path, branch.to_string()));
+                }
+            }
+        }
+
+        Ok(worktrees)
+    }
+
+    fn delete_branch(&self, name: &str, force: bool) -> Result<(), Error> {
+        self.run_git_for_deletion(["branch", if force { "-D" } else { "-d" }, name])
+    }
+
+    fn delete_remote_tracking_branch(&self, name: &str) -> Result<(), Error> {
+        self.run_git_for_deletion(["branch", "--delete", "--remotes", name])
+    }
+
+    fn set_upstream(&self, name: &str, remote: &str, merge: &str) -> Result<(), Error> {
+        self.run_git_for_update(["config", &format!("branch.{}.remote", name), remote])?;
+        self.run_git_for_update(["config", &format!("branch.{}.merge", name), merge])
+    }
+
+    fn references(&self, prefix: &str) -> Result<Vec<(String, String)>, Error> {
+        Ok(self
+            .run_git_for_lines(&["for-each-ref", "--format=%(refname) %(objectname)", prefix])?
+            .into_iter()
+            .filter_map(|line| {
+                let (name, id) = line.rsplit_once(' ')?;
+                Some((name.to_string(), id.to_string()))
+            })
+            .collect())
+    }
+
+    fn set_reference(&self, name: &str, id: &str) -> Result<(), Error> {
+        self.run_git_for_update(["update-ref", name, id])
+    }
+
+    fn delete_reference(&self, name: &str) -> Result<(), Error> {
+        self.run_git_for_update(["update-ref", "-d", name])
+    }
+
+    fn fetch(&self, remote: &str) -> Result<bool, Error> {
+        self.run_git_for_status(&["fetch", "--prune", "--quiet", remote])
+    }
+
+    fn remote_heads(&self, remote: &str) -> Result<Option<Vec<String>>, Error> {
+        let output = self
+            .git()
+            .arg("ls-remote")
+            .arg("--heads")
+            .arg(remote)
+            .output()?;
+
+        if !output.status.success() {
+            return Ok(None);
+        }
+
+        Ok(Some(
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter_map(|line| line.split_once('\t'))
+                .filter_map(|(_, name)| name.strip_prefix("refs/heads/"))
+                .map(String::from)
+                .collect(),
+        ))
+    }
+
+    fn delete_remote_branch(&self, remote: &str, branch: &str) -> Result<(), Error> {
+        self.run_git_for_deletion([
+            "push",
+            "--quiet",
+            "--delete",
+            &format!("--force-with-lease={}", branch),
+            remote,
+            branch,
+        ])
+    }
+
+    fn rebase_merged_commits(&self, target: &str, branch: &str) -> Result<Option<usize>, Error> {
+        let cherry = match self.run_git_for_output(&["cherry", target, branch])? {
+            Some(cherry) => cherry,
+            None => return Ok(None),
+        };
+
+        let commits: Vec<&str> = cherry.lines().collect();
+
+        if commits.iter().all(|commit| commit.starts_with('-')) {
+            Ok(Some(commits.len()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// A temporary commit squashing the whole branch onto the merge base is created, then
+    /// `git cherry` tells whether an equivalent patch already exists on the target branch.
+    fn is_squash_merged(&self, target: &str, branch: &str) -> Result<bool, Error> {
+        let merge_base = match self.run_git_for_output(&["merge-base", target, branch])? {
+            Some(merge_base) => merge_base,
+            None => return Ok(false),
+        };
+
+        let tree = match self.run_git_for_output(&["rev-parse", &format!("{}^{{tree}}", branch)])? {
+            Some(tree) => tree,
+            None => return Ok(false),
+        };
+
+        let output = self
+            .git()
+            .arg("commit-tree")
+            .arg(&tree)
+            .arg("-p")
+            .arg(&merge_base)
+            .arg("-m")
+            .arg("git-broom squash check")
+            .env("GIT_AUTHOR_NAME", "git-broom")
+            .env("GIT_AUTHOR_EMAIL", "git-broom@localhost")
+            .env("GIT_COMMITTER_NAME", "git-broom")
+            .env("GIT_COMMITTER_EMAIL", "git-broom@localhost")
+            .output()?;
+
+        if !output.status.success() {
+            return Ok(false);
+        }
+
+        let squashed_commit = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+        match self.run_git_for_output(&["cherry", target, &squashed_commit])? {
+            Some(cherry) => Ok(cherry.starts_with('-')),
+            None => Ok(false),
+        }
+    }
+
+    fn unmerged_commits(&self, branch: &str, targets: &[String]) -> Result<Vec<Commit>, Error> {
+        let mut args = vec!["log", "--format=%H %s", branch, "--not"];
+        args.extend(targets.iter().map(String::as_str));
+        args.push("--");
+
+        Ok(self
+            .run_git_for_lines(&args)?
+            .into_iter()
+            .map(|line| {
+                let (id, summary) = line.split_once(' ').unwrap_or((&line, ""));
+
+                Commit {
+                    id: id.to_string(),
+                    summary: summary.to_string(),
+                }
+            })
+            .collect())
+    }
+
+    fn create_bundle(&self, path: &Path, references: &[String]) -> Result<(), Error> {
+        let mut args = vec![
+            OsStr::new("bundle"),
+            OsStr::new("create"),
+            OsStr::new("--quiet"),
+            path.as_os_str(),
+        ];
+        args.extend(references.iter().map(OsStr::new));
+
+        self.run_git_for_update(args)
+    }
+
+    fn verify_bundle(&self, path: &Path) -> Result<(), Error> {
+        self.run_git_for_update([
+            OsStr::new("bundle"),
+            OsStr::new("verify"),
+            OsStr::new("--quiet"),
+            path.as_os_str(),
+        ])
+    }
+
+    fn is_worktree_clean(&self, worktree: &Path) -> Result<bool, Error> {
+        let status = self
+            .git()
+            .arg("-C")
+            .arg(worktree)
+            .arg("status")
+            .arg("--porcelain")
+            .output()?;
+
+        Ok(status.status.success() && status.stdout.is_empty())
+    }
+
+    fn remove_worktree(&self, worktree: &Path) -> Result<(), Error> {
+        self.run_git_for_deletion([
+            OsStr::new("worktree"),
+            OsStr::new("remove"),
+            worktree.as_os_str(),
+        ])
+    }
+}
+
+/// Tells why Git refused to delete something from its diagnostic, in the C locale.
+fn deletion_error_kind(message: &str) -> DeletionErrorKind {
+    if message.contains("not fully merged") {
+        DeletionErrorKind::NotFullyMerged
+    } else if message.contains("used by worktree") || message.contains("checked out at") {
+        DeletionErrorKind::CheckedOut
+    } else if message.contains("cannot lock ref") || message.contains(".lock': File exists") {
+        DeletionErrorKind::RefLocked
+    } else if message.contains("[rejected]") || message.contains("[remote rejected]") {
+        DeletionErrorKind::RemoteRejected
+    } else if message.contains("not found") {
+        DeletionErrorKind::NotFound
+    } else {
+        DeletionErrorKind::Other
+    }
+}
@@ -0,0 +1,507 @@
+/*
+Git Broom
+Copyright (C) 2024  All contributors.
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use gix::bstr::{BStr, ByteSlice};
+use gix::refs::TargetRef;
+use gix::remote::Direction;
+use gix::ObjectId;
+
+use super::{Backend, LocalBranch};
+use crate::branch::Commit;
+use crate::error::{DeletionError, DeletionErrorKind, Error};
+
+/// Works in-process with gitoxide, without needing Git to be installed.
+///
+/// Fetching, pushing, detecting squash-merged and rebase-merged branches, and removing worktrees
+/// are not supported.
+#[derive(Debug)]
+pub struct GixBackend {
+    /// The path the repository is discovered from.
+    path: PathBuf,
+    repository: OnceLock<gix::ThreadSafeRepository>,
+}
+
+impl GixBackend {
+    /// Creates a backend for the repository containing `path`, discovered the way Git does and
+    /// opened on first use.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            repository: OnceLock::new(),
+        }
+    }
+
+    fn repository(&self) -> Result<gix::Repository, Error> {
+        if let Some(repository) = self.repository.get() {
+            return Ok(repository.to_thread_local());
+        }
+
+        let repository = gix::ThreadSafeRepository::discover_with_environment_overrides(&self.path)
+            .map_err(|_| Error::NotARepository)?;
+
+        Ok(self.repository.get_or_init(|| repository).to_thread_local())
+    }
+
+    fn resolve(repository: &gix::Repository, revision: &str) -> Result<Option<ObjectId>, Error> {
+        let id = match repository.rev_parse_single(revision) {
+            Ok(id) => id,
+            Err(_) => return Ok(None),
+        };
+
+        let object = id.object().map_err(Error::backend)?;
+
+        Ok(object.peel_to_commit().ok().map(|commit| commit.id))
+    }
+
+    /// Deletes a reference, failing like Git does if it does not exist or is locked. `kind` tells
+    /// what the reference is in the diagnostic, such as `branch`.
+    fn remove_reference(&self, name: &str, kind: &str) -> Result<(), Error> {
+        use gix::refs::file::transaction::prepare;
+
+        let short_name = short_name(name.as_bytes().as_bstr());
+        let repository = self.repository()?;
+        let reference = repository
+            .try_find_reference(name)
+            .map_err(Error::backend)?
+            .ok_or_else(|| {
+                DeletionError::new(
+                    DeletionErrorKind::NotFound,
+                    format!("error: {} '{}' not found", kind, short_name),
+                )
+            })?;
+
+        match reference.delete() {
+            Ok(()) => Ok(()),
+            Err(gix::reference::edit::Error::FileTransactionPrepare(
+                e @ (prepare::Error::LockAcquire { .. }
+                | prepare::Error::PackedTransactionAcquire(_)),
+            )) => Err(DeletionError::new(
+                DeletionErrorKind::RefLocked,
+                format!("error: cannot lock ref '{}': {}", name, e),
+            )
+            .into()),
+            Err(e) => Err(Error::backend(e)),
+        }
+    }
+
+    fn local_config(&self) -> Result<gix_config::File<'static>, Error> {
+        let path = self.repository()?.common_dir().join("config");
+
+        gix_config::File::from_path_no_includes(path, gix_config::Source::Local)
+            .map_err(Error::backend)
+    }
+
+    /// Removes the `branch.<name>` sections of the local configuration, as Git does when deleting
+    /// a branch.
+    fn remove_branch_config(&self, name: &str) -> Result<(), Error> {
+        let mut config = self.local_config()?;
+
+        let mut removed = false;
+        while config
+            .remove_section("branch", Some(name.as_bytes().as_bstr()))
+            .is_some()
+        {
+            removed = true;
+        }
+
+        if !removed {
+            return Ok(());
+        }
+
+        self.write_local_config(&config)
+    }
+
+    /// Writes the local configuration, taking the `config.lock` lock file the way Git does.
+    fn write_local_config(&self, config: &gix_config::File<'static>) -> Result<(), Error> {
+        let common_dir = self.repository()?.common_dir().to_path_buf();
+        let path = common_dir.join("config");
+
+        let lock_path = common_dir.join("config.lock");
+        let mut lock = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&lock_path)?;
+
+        let written = config
+            .write_to(&mut lock)
+            .and_then(|_| lock.flush())
+            .and_then(|_| fs::rename(&lock_path, &path));
+
+        if written.is_err() {
+            let _ = fs::remove_file(&lock_path);
+        }
+
+        Ok(written?)
+    }
+}
+
+fn short_name(name: &BStr) -> String {
+    let name = name.to_str_lossy();
+
+    name.strip_prefix("refs/heads/")
+        .or_else(|| name.strip_prefix("refs/remotes/"))
+        .unwrap_or(&name)
+        .to_string()
+}
+
+fn canonical_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+impl Backend for GixBackend {
+    fn git_directories(&self) -> Result<(PathBuf, PathBuf), Error> {
+        let repository = self.repository()?;
+
+        Ok((
+            canonical_path(repository.git_dir()),
+            canonical_path(repository.common_dir()),
+        ))
+    }
+
+    fn head(&self) -> Result<Option<String>, Error> {
+        Ok(self
+            .repository()?
+            .head_name()
+            .map_err(Error::backend)?
+            .map(|name| name.as_bstr().to_string()))
+    }
+
+    fn remotes(&self) -> Result<Vec<String>, Error> {
+        Ok(self
+            .repository()?
+            .remote_names()
+            .iter()
+            .map(|name| name.to_string())
+            .collect())
+    }
+
+    fn local_branches(&self) -> Result<Vec<LocalBranch>, Error> {
+        let repository = self.repository()?;
+        let config = repository.config_snapshot();
+        let references = repository.references().map_err(Error::backend)?;
+
+        let mut branches: Vec<LocalBranch> = Vec::new();
+
+        for reference in references.local_branches().map_err(Error::backend)? {
+            let reference = reference.map_err(Error::backend)?;
+            let name = reference.name();
+
+            let mut branch = LocalBranch {
+                name: short_name(name.as_bstr()),
+                ..Default::default()
+            };
+
+            let remote = config
+                .plumbing()
+                .string_by("branch", Some(name.shorten()), "remote");
+            let merge = repository.branch_remote_ref_name(name, Direction::Fetch);
+
+            if let (Some(remote), Some(Ok(merge))) = (remote, merge) {
+                let upstream = if remote.as_ref() == "." {
+                    Some(merge.as_bstr().to_owned())
+                } else {
+                    match repository.branch_remote_tracking_ref_name(name, Direction::Fetch) {
+                        Some(Ok(tracking)) => Some(tracking.as_bstr().to_owned()),
+                        _ => None,
+                    }
+                };
+
+                if let Some(upstream) = upstream {
+                    branch.upstream_gone = repository
+                        .try_find_reference(upstream.as_bstr())
+                        .map_err(Error::backend)?
+                        .is_none();
+                    branch.upstream = Some(short_name(upstream.as_bstr()));
+                }
+
+                branch.upstream_remote = Some(remote.to_string());
+            }
+
+            branches.push(branch);
+        }
+
+        Ok(branches)
+    }
+
+    fn remote_tracking_branches(&self) -> Result<Vec<String>, Error> {
+        let repository = self.repository()?;
+        let references = repository.references().map_err(Error::backend)?;
+
+        let mut branches: Vec<String> = Vec::new();
+
+        for reference in references.remote_branches().map_err(Error::backend)? {
+            let reference = reference.map_err(Error::backend)?;
+
+            if let TargetRef::Object(_) = reference.target() {
+                branches.push(reference.name().as_bstr().to_string());
+            }
+        }
+
+        Ok(branches)
+    }
+
+    fn symbolic_ref_target(&self, name: &str) -> Result<Option<String>, Error> {
+        let repository = self.repository()?;
+
+        Ok(
+            match repository
+                .try_find_reference(name)
+                .map_err(Error::backend)?
+            {
+                Some(reference) => match reference.target() {
+                    TargetRef::Symbolic(target) => Some(target.as_bstr().to_string()),
+                    TargetRef::Object(_) => None,
+                },
+                None => None,
+            },
+        )
+    }
+
+    fn resolve_commit(&self, revision: &str) -> Result<Option<String>, Error> {
+        Ok(Self::resolve(&self.repository()?, revision)?.map(|id| id.to_string()))
+    }
+
+    fn is_ancestor(&self, ancestor: &str, descendant: &str) -> Result<bool, Error> {
+        let repository = self.repository()?;
+
+        let (ancestor, descendant) = match (
+            Self::resolve(&repository, ancestor)?,
+            Self::resolve(&repository, descendant)?,
+        ) {
+            (Some(ancestor), Some(descendant)) => (ancestor, descendant),
+            _ => return Ok(false),
+        };
+
+        if ancestor == descendant {
+            return Ok(true);
+        }
+
+        match repository.merge_base(ancestor, descendant) {
+            Ok(merge_base) => Ok(merge_base.detach() == ancestor),
+            Err(gix::repository::merge_base::Error::NotFound { .. }) => Ok(false),
+            Err(e) => Err(Error::backend(e)),
+        }
+    }
+
+    fn count_commits(&self, from: &str, to: &str) -> Result<usize, Error> {
+        let repository = self.repository()?;
+
+        let (from, to) = match (
+            Self::resolve(&repository, from)?,
+            Self::resolve(&repository, to)?,
+        ) {
+            (Some(from), Some(to)) => (from, to),
+            _ => return Ok(0),
+        };
+
+        let walk = repository
+            .rev_walk([to])
+            .with_hidden([from])
+            .all()
+            .map_err(Error::backend)?;
+
+        let mut count = 0;
+        for commit in walk {
+            commit.map_err(Error::backend)?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    fn unmerged_commits(&self, branch: &str, targets: &[String]) -> Result<Vec<Commit>, Error> {
+        let repository = self.repository()?;
+
+        let tip = match Self::resolve(&repository, branch)? {
+            Some(tip) => tip,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut hidden: Vec<ObjectId> = Vec::new();
+        for target in targets {
+            hidden.extend(Self::resolve(&repository, target)?);
+        }
+
+        let walk = repository
+            .rev_walk([tip])
+            .with_hidden(hidden)
+            .all()
+            .map_err(Error::backend)?;
+
+        let mut commits: Vec<Commit> = Vec::new();
+        for info in walk {
+            let commit = info
+                .map_err(Error::backend)?
+                .object()
+                .map_err(Error::backend)?;
+            let message = commit.message().map_err(Error::backend)?;
+
+            commits.push(Commit {
+                id: commit.id.to_string(),
+                summary: message.summary().to_string(),
+            });
+        }
+
+        Ok(commits)
+    }
+
+    fn worktrees(&self) -> Result<Vec<(PathBuf, String)>, Error> {
+        let repository = self.repository()?;
+        let main = repository.main_repo().map_err(Error::backend)?;
+
+        let mut worktrees: Vec<(PathBuf, String)> = Vec::new();
+
+        if let (Some(path), Some(head)) =
+            (main.workdir(), main.head_name().map_err(Error::backend)?)
+        {
+            if let Some(branch) = head.as_bstr().strip_prefix(b"refs/heads/") {
+                worktrees.push((canonical_path(path), branch.to_str_lossy().to_string()));
+            }
+        }
+
+        for worktree in repository.worktrees()? {
+            let head = fs::read_to_string(worktree.git_dir().join("HEAD"))?;
+
+            if let Some(branch) = head.trim().strip_prefix("ref: refs/heads/") {
+                worktrees.push((canonical_path(&worktree.base()?), branch.to_string()));
+            }
+        }
+
+        Ok(worktrees)
+    }
+
+    fn delete_branch(&self, name: &str, force: bool) -> Result<(), Error> {
+        let full_name = format!("refs/heads/{}", name);
+
+        // Like Git, never delete a branch checked out in a worktree.
+        let current_worktree = self.git_directories()?.0;
+        if let Some(path) = self
+            .worktrees()?
+            .into_iter()
+            .find(|(_, branch)| branch.eq(name))
+            .map(|(path, _)| path)
+            .or_else(|| {
+                (self.head().ok()?.as_deref() == Some(full_name.as_str()))
+                    .then_some(current_worktree)
+            })
+        {
+            return Err(DeletionError::new(
+                DeletionErrorKind::CheckedOut,
+                format!(
+                    "error: cannot delete branch '{}' used by worktree at '{}'",
+                    name,
+                    path.display()
+                ),
+            )
+            .into());
+        }
+
+        if self.resolve_commit(&full_name)?.is_none() {
+            return Err(DeletionError::new(
+                DeletionErrorKind::NotFound,
+                format!("error: branch '{}' not found", name),
+            )
+            .into());
+        }
+
+        if !force {
+            let base = self
+                .local_branches()?
+                .into_iter()
+                .find(|branch| branch.name.eq(name))
+                .and_then(|branch| branch.upstream.filter(|_| !branch.upstream_gone))
+                .unwrap_or_else(|| String::from("HEAD"));
+
+            if !self.is_ancestor(&full_name, &base)? {
+                return Err(DeletionError::new(
+                    DeletionErrorKind::NotFullyMerged,
+                    format!("error: the branch '{}' is not fully merged", name),
+                )
+                .into());
+            }
+        }
+
+        self.remove_reference(&full_name, "branch")?;
+        self.remove_branch_config(name)
+    }
+
+    fn delete_remote_tracking_branch(&self, name: &str) -> Result<(), Error> {
+        self.remove_reference(&format!("refs/remotes/{}", name), "remote-tracking branch")
+    }
+
+    fn set_upstream(&self, name: &str, remote: &str, merge: &str) -> Result<(), Error> {
+        let mut config = self.local_config()?;
+        let subsection = Some(name.as_bytes().as_bstr());
+
+        config
+            .set_raw_value_by("branch", subsection, "remote", remote)
+            .map_err(Error::backend)?;
+        config
+            .set_raw_value_by("branch", subsection, "merge", merge)
+            .map_err(Error::backend)?;
+
+        self.write_local_config(&config)
+    }
+
+    fn references(&self, prefix: &str) -> Result<Vec<(String, String)>, Error> {
+        let repository = self.repository()?;
+        let references = repository.references().map_err(Error::backend)?;
+
+        let mut found: Vec<(String, String)> = Vec::new();
+
+        for reference in references.prefixed(prefix).map_err(Error::backend)? {
+            let reference = reference.map_err(Error::backend)?;
+
+            if let TargetRef::Object(id) = reference.target() {
+                found.push((reference.name().as_bstr().to_string(), id.to_string()));
+            }
+        }
+
+        Ok(found)
+    }
+
+    fn set_reference(&self, name: &str, id: &str) -> Result<(), Error> {
+        let id = ObjectId::from_hex(id.as_bytes()).map_err(Error::backend)?;
+
+        // Like Git, write the reflog with a generic identity if none is configured.
+        let mut repository = self.repository()?;
+        repository
+            .committer_or_set_generic_fallback()
+            .map_err(Error::backend)?;
+
+        repository
+            .reference(
+                name,
+                id,
+                gix::refs::transaction::PreviousValue::Any,
+                "git-broom",
+            )
+            .map_err(Error::backend)?;
+
+        Ok(())
+    }
+
+    fn delete_reference(&self, name: &str) -> Result<(), Error> {
+        self.remove_reference(name, "reference")
+    }
+}
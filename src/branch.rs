@@ -0,0 +1,91 @@
+/*
+Git Broom
+Copyright (C) 2024  All contributors.
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use std::path::PathBuf;
+
+use regex::Regex;
+
+/// A branch found by a scan, along with why it can be deleted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Branch {
+    /// The short name of the branch, such as `feature` or `origin/feature`.
+    pub name: String,
+    /// Whether the branch matches a protection rule.
+    pub protected: bool,
+    pub status: MergeStatus,
+    /// The target branches the branch was found merged on.
+    pub targets: Vec<String>,
+    /// The remote holding the branch, for a remote branch.
+    pub remote: Option<String>,
+    /// The linked worktree in which the branch is checked out, if any.
+    pub worktree: Option<PathBuf>,
+    /// The id of the commit the branch points to when it was scanned.
+    pub tip: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MergeStatus {
+    /// The branch tip is an ancestor of the target branch.
+    Merged,
+    /// The cumulative diff of the branch exists as a single commit on the target branch.
+    SquashMerged,
+    /// Every commit unique to the branch has a patch-equivalent commit on the target branch.
+    /// Holds the number of matched commits.
+    RebaseMerged(usize),
+    /// The branch is not found merged, but its upstream branch no longer exists on the remote.
+    UpstreamGone,
+    /// The remote-tracking branch belongs to a remote which is no longer configured.
+    RemoteRemoved,
+    /// The remote-tracking branch no longer exists on its remote.
+    StaleRemoteBranch,
+}
+
+impl Branch {
+    /// Whether the branch is a local remote-tracking branch, rather than a local or a remote branch.
+    pub fn is_stale_ref(&self) -> bool {
+        self.status == MergeStatus::RemoteRemoved || self.status == MergeStatus::StaleRemoteBranch
+    }
+
+    /// Returns the full name of the local reference of the branch, such as `refs/heads/feature`,
+    /// or `refs/remotes/origin/feature` for a remote branch or a remote-tracking branch.
+    pub fn ref_name(&self) -> String {
+        if self.remote.is_some() || self.is_stale_ref() {
+            format!("refs/remotes/{}", self.name)
+        } else {
+            format!("refs/heads/{}", self.name)
+        }
+    }
+}
+
+/// A commit, as listed before it is lost by force deleting a branch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Commit {
+    pub id: String,
+    /// The first line of the commit message.
+    pub summary: String,
+}
+
+/// A regular expression protecting branches from deletion.
+#[derive(Clone, Debug)]
+pub struct ProtectionRule {
+    /// The configuration key the rule is read from, such as `broom.protectedbranches`.
+    pub key: String,
+    pub pattern: Regex,
+    /// The configuration file the rule comes from.
+    pub origin: String,
+}
@@ -0,0 +1,1137 @@
+/*
+Git Broom
+Copyright (C) 2024  All contributors.
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use std::cell::Cell;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::slice;
+
+use clap::ValueEnum;
+use colored::*;
+use git_broom::i18n::Localization;
+use git_broom::{
+    ArchivedBranch, Branch, Bundle, DeletionError, DeletionErrorKind, DeletionOutcome,
+    DeletionStatus, Error, GitBroom, JournalEntry, MergeStatus, Notice, Report,
+};
+use regex::Regex;
+
+use crate::json::JsonReport;
+
+/// How the branches found are printed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Localized text, proposing to delete the branches.
+    Human,
+    /// A JSON document, printed once the branches are deleted with `--yes`, never asking anything.
+    Json,
+    /// One line per branch, with tab-separated fields which are neither localized nor coloured.
+    /// Nothing is deleted.
+    #[value(skip)]
+    Porcelain {
+        /// Terminate the lines with NUL instead of a newline.
+        null_terminated: bool,
+    },
+}
+
+/// What [`Cli::broom`] found and did.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Summary {
+    /// The number of branches proposed for deletion.
+    pub proposed: usize,
+    /// The number of protected branches which cannot be deleted, among the selected ones.
+    pub protected: usize,
+    /// The number of branches which could not be deleted.
+    pub failed: usize,
+}
+
+/// How [`Cli`] proposes to delete branches, as given on the command line.
+#[derive(Debug)]
+pub struct CliOptions {
+    pub dry_run: bool,
+    pub include_protected_branches: bool,
+    pub yes: bool,
+    pub force_unmerged: bool,
+    pub bundle: Option<PathBuf>,
+    pub select: Vec<Regex>,
+    pub format: Format,
+}
+
+/// Prints the branches found by a [`GitBroom`], and proposes to delete them.
+pub struct Cli {
+    broom: GitBroom,
+    localization: Localization,
+    dry_run: bool,
+    include_protected_branches: bool,
+    /// Delete the selected branches without asking.
+    yes: bool,
+    /// Offer to force delete the branches refused because they are not fully merged.
+    force_unmerged: bool,
+    /// Save the branches proposed for deletion to this bundle before deleting any.
+    bundle: Option<PathBuf>,
+    /// Only the branches matching any of these patterns are deleted, all of them if empty.
+    select: Vec<Regex>,
+    format: Format,
+    failed: Cell<usize>,
+}
+
+impl Cli {
+    pub fn new(broom: GitBroom, options: CliOptions) -> Self {
+        let CliOptions {
+            dry_run,
+            include_protected_branches,
+            yes,
+            force_unmerged,
+            bundle,
+            select,
+            format,
+        } = options;
+
+        Self {
+            broom,
+            localization: Localization::new(),
+            dry_run,
+            include_protected_branches,
+            yes,
+            force_unmerged,
+            bundle,
+            select,
+            format,
+            failed: Cell::new(0),
+        }
+    }
+
+    pub fn broom(&self) -> Result<Summary, Error> {
+        let report = self.broom.scan()?;
+
+        match self.format {
+            Format::Human => {
+                let bundle = self.bundle_branches(&report)?;
+                self.broom_human(&report, bundle.as_ref())?
+            }
+            Format::Json => {
+                let bundle = if self.yes {
+                    self.bundle_branches(&report)?
+                } else {
+                    None
+                };
+                self.broom_json(&report, bundle.as_ref())?
+            }
+            Format::Porcelain { null_terminated } => {
+                self.broom_porcelain(&report, null_terminated)?
+            }
+        }
+
+        Ok(Summary {
+            proposed: report
+                .branches()
+                .filter(|branch| self.is_proposed(branch))
+                .count(),
+            protected: report
+                .branches()
+                .filter(|branch| {
+                    !self.include_protected_branches && branch.protected && self.is_selected(branch)
+                })
+                .count(),
+            failed: self.failed.get(),
+        })
+    }
+
+    /// Saves the branches proposed for deletion to the bundle, if one is asked for and branches
+    /// may be deleted, before any is.
+    fn bundle_branches(&self, report: &Report) -> Result<Option<Bundle>, Error> {
+        let path = match &self.bundle {
+            Some(path) if !self.dry_run => path,
+            _ => return Ok(None),
+        };
+
+        let branches: Vec<Branch> = report
+            .branches()
+            .filter(|branch| self.is_proposed(branch))
+            .cloned()
+            .collect();
+
+        if branches.is_empty() {
+            return Ok(None);
+        }
+
+        self.broom.bundle(path, &branches).map(Some)
+    }
+
+    fn broom_human(&self, report: &Report, bundle: Option<&Bundle>) -> Result<(), Error> {
+        self.print_notices(&report.notices);
+
+        if let Some(bundle) = bundle {
+            println!(
+                "{}",
+                self.localization.get_message_with_count_and_one_arg(
+                    "branches-bundled",
+                    bundle.references.len(),
+                    String::from("path"),
+                    bundle.path.display().to_string().bold().to_string(),
+                )
+            );
+
+            for reference in &bundle.references {
+                println!("  * {}", reference.dimmed());
+            }
+
+            println!();
+        }
+
+        if let Some(remote) = &report.remote {
+            self.broom_remote_branch(remote, report)?;
+        } else {
+            self.broom_branch(report)?;
+        }
+
+        if let Some(stale_refs) = &report.stale {
+            println!();
+            self.broom_stale_refs(stale_refs)?;
+        }
+
+        Ok(())
+    }
+
+    /// Deletes the branches proposed for deletion if every deletion is confirmed, then prints the
+    /// report along with the outcomes as JSON.
+    fn broom_json(&self, report: &Report, bundle: Option<&Bundle>) -> Result<(), Error> {
+        let outcomes = if self.yes && !self.dry_run {
+            let branches: Vec<Branch> = report
+                .branches()
+                .filter(|branch| self.is_proposed(branch))
+                .cloned()
+                .collect();
+
+            self.broom.apply(&branches)?
+        } else {
+            Vec::new()
+        };
+
+        for outcome in &outcomes {
+            self.count_failure(&outcome.status);
+        }
+
+        println!(
+            "{}",
+            JsonReport::new(report, |branch| self.is_proposed(branch), &outcomes, bundle).to_json()
+        );
+
+        Ok(())
+    }
+
+    /// Prints a record per branch: its name, category, reason, whether it is protected and proposed
+    /// for deletion, its tip, the target branches it is merged on and its worktree.
+    fn broom_porcelain(&self, report: &Report, null_terminated: bool) -> Result<(), Error> {
+        let categories = [
+            ("merged", Some(&report.merged)),
+            ("gone", report.gone.as_ref()),
+            ("stale", report.stale.as_ref()),
+        ];
+
+        let mut stdout = io::stdout().lock();
+
+        for (category, branches) in categories {
+            for branch in branches.into_iter().flatten() {
+                let fields = [
+                    branch.name.as_str(),
+                    category,
+                    match branch.status {
+                        MergeStatus::Merged => "merged",
+                        MergeStatus::SquashMerged => "squash_merged",
+                        MergeStatus::RebaseMerged(_) => "rebase_merged",
+                        MergeStatus::UpstreamGone => "upstream_gone",
+                        MergeStatus::RemoteRemoved => "remote_removed",
+                        MergeStatus::StaleRemoteBranch => "stale_remote_branch",
+                    },
+                    if branch.protected {
+                        "protected"
+                    } else {
+                        "unprotected"
+                    },
+                    if self.is_proposed(branch) {
+                        "selected"
+                    } else {
+                        "unselected"
+                    },
+                    branch.tip.as_deref().unwrap_or_default(),
+                    &branch.targets.join(","),
+                    &branch
+                        .worktree
+                        .as_ref()
+                        .map(|worktree| worktree.display().to_string())
+                        .unwrap_or_default(),
+                ];
+
+                write!(
+                    stdout,
+                    "{}{}",
+                    fields.join("\t"),
+                    if null_terminated { '\0' } else { '\n' }
+                )?;
+            }
+        }
+
+        Ok(stdout.flush()?)
+    }
+
+    fn print_notices(&self, notices: &[Notice]) {
+        for notice in notices {
+            match notice {
+                Notice::DefaultBranch { branch, source } => println!(
+                    "{}",
+                    self.localization.get_message_with_two_args(
+                        "using-default-branch",
+                        (String::from("branch"), branch.bold().to_string()),
+                        (String::from("source"), source.to_string()),
+                    )
+                ),
+                Notice::NoUpstream { branch } => println!(
+                    "{}",
+                    self.localization
+                        .get_message_with_one_arg(
+                            "no-upstream",
+                            String::from("branch"),
+                            branch.bold().to_string(),
+                        )
+                        .yellow()
+                ),
+                Notice::BehindUpstream {
+                    branch,
+                    upstream,
+                    commits,
+                } => {
+                    let args = vec![
+                        (String::from("branch"), branch.bold().to_string()),
+                        (String::from("upstream"), upstream.bold().to_string()),
+                    ];
+
+                    println!(
+                        "{}",
+                        self.localization
+                            .get_message_with_count_and_args(
+                                "target-behind-upstream",
+                                *commits,
+                                args
+                            )
+                            .yellow()
+                    );
+                }
+                Notice::Fetched { remote, pruned } => {
+                    println!(
+                        "{}",
+                        self.localization.get_message_with_count_and_one_arg(
+                            "fetched-remote",
+                            pruned.len(),
+                            String::from("remote"),
+                            remote.bold().to_string(),
+                        )
+                    );
+
+                    for pruned_ref in pruned {
+                        println!("  - {}", pruned_ref.dimmed());
+                    }
+                }
+                Notice::FetchFailed { remote } => println!(
+                    "{}",
+                    self.localization
+                        .get_message_with_one_arg(
+                            "fetch-failed",
+                            String::from("remote"),
+                            remote.bold().to_string(),
+                        )
+                        .yellow()
+                ),
+                Notice::RemoteUnreachable { remote } => println!(
+                    "{}",
+                    self.localization
+                        .get_message_with_one_arg(
+                            "ls-remote-failed",
+                            String::from("remote"),
+                            remote.bold().to_string(),
+                        )
+                        .yellow()
+                ),
+            }
+        }
+
+        if !notices.is_empty() {
+            println!();
+        }
+    }
+
+    fn broom_branch(&self, report: &Report) -> Result<(), Error> {
+        let targets = &report.targets;
+
+        if !report.merged.is_empty() {
+            self.broom_branches(
+                &report.merged,
+                targets,
+                "found-merged",
+                "found-merged-protected",
+            )?;
+        } else {
+            println!(
+                "{}",
+                self.localization.get_message_with_one_arg(
+                    "no-merged-branch",
+                    String::from("branch"),
+                    targets.join(", ").bold().to_string(),
+                )
+            );
+        }
+
+        if let Some(gone_branches) = &report.gone {
+            println!();
+
+            if !gone_branches.is_empty() {
+                self.broom_branches(gone_branches, targets, "found-gone", "found-gone-protected")?;
+            } else {
+                println!("{}", self.localization.get_message("no-gone-branch"));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn broom_remote_branch(&self, remote: &str, report: &Report) -> Result<(), Error> {
+        if !report.merged.is_empty() {
+            self.broom_branches(
+                &report.merged,
+                &report.targets,
+                "found-merged-remote",
+                "found-merged-remote-protected",
+            )?;
+        } else {
+            println!(
+                "{}",
+                self.localization.get_message_with_two_args(
+                    "no-merged-remote-branch",
+                    (String::from("remote"), remote.bold().to_string()),
+                    (
+                        String::from("branch"),
+                        report.targets.join(", ").bold().to_string()
+                    ),
+                )
+            );
+        }
+
+        Ok(())
+    }
+
+    fn broom_stale_refs(&self, stale_refs: &[Branch]) -> Result<(), Error> {
+        if !stale_refs.is_empty() {
+            self.broom_branches(stale_refs, &[], "found-stale", "found-stale-protected")?;
+        } else {
+            println!("{}", self.localization.get_message("no-stale-ref"));
+        }
+
+        Ok(())
+    }
+
+    /// Lists a category of branches, protected ones first, then proposes to delete the others.
+    ///
+    /// `found_message` and `found_protected_message` are the headers of the lists, receiving the
+    /// number of branches and the target branches as arguments.
+    fn broom_branches(
+        &self,
+        branches: &[Branch],
+        targets: &[String],
+        found_message: &str,
+        found_protected_message: &str,
+    ) -> Result<(), Error> {
+        let branch = targets.join(", ");
+
+        let protected_branches: Vec<Branch> = branches
+            .iter()
+            .filter(|branch| !self.include_protected_branches && branch.protected)
+            .cloned()
+            .collect();
+
+        let not_protected_branches: Vec<Branch> = branches
+            .iter()
+            .filter(|branch| self.include_protected_branches || !branch.protected)
+            .cloned()
+            .collect();
+
+        if !protected_branches.is_empty() {
+            println!(
+                "{}",
+                self.localization.get_message_with_count_and_one_arg(
+                    found_protected_message,
+                    protected_branches.len(),
+                    String::from("branch"),
+                    branch.bold().underline().to_string()
+                )
+            );
+
+            for branch in &protected_branches {
+                println!(
+                    "  * {}{}",
+                    branch.name.blue(),
+                    self.get_branch_labels(branch, targets)
+                );
+            }
+
+            println!(
+                "{}",
+                self.localization
+                    .get_message_with_count("branches-wont-be-deleted", protected_branches.len())
+            );
+
+            if !not_protected_branches.is_empty() {
+                println!();
+            }
+        }
+
+        if !not_protected_branches.is_empty() {
+            println!(
+                "{}",
+                self.localization.get_message_with_count_and_one_arg(
+                    found_message,
+                    not_protected_branches.len(),
+                    String::from("branch"),
+                    branch.bold().underline().to_string(),
+                )
+            );
+
+            for branch in &not_protected_branches {
+                if !self.is_selected(branch) {
+                    println!(
+                        "  * {} {}{}",
+                        branch.name.dimmed(),
+                        self.localization.get_message("not-selected").dimmed(),
+                        self.get_branch_labels(branch, targets)
+                    );
+                } else if branch.protected {
+                    println!(
+                        "  * {} {}{}",
+                        branch.name.red(),
+                        self.localization.get_message("protected").red(),
+                        self.get_branch_labels(branch, targets)
+                    );
+                } else {
+                    println!(
+                        "  * {}{}",
+                        branch.name.green(),
+                        self.get_branch_labels(branch, targets)
+                    );
+                }
+            }
+
+            if !self.dry_run {
+                let selected_branches: Vec<Branch> = not_protected_branches
+                    .into_iter()
+                    .filter(|branch| self.is_selected(branch))
+                    .collect();
+
+                if selected_branches.is_empty() {
+                    println!("{}", self.localization.get_message("no-branch-selected"));
+                    return Ok(());
+                }
+
+                if let Some(remote) = selected_branches
+                    .iter()
+                    .find_map(|branch| branch.remote.as_ref())
+                {
+                    println!();
+                    println!(
+                        "{}",
+                        self.localization
+                            .get_message_with_one_arg(
+                                "remote-deletion-warning",
+                                String::from("remote"),
+                                remote.to_string(),
+                            )
+                            .red()
+                            .bold()
+                    );
+                }
+
+                if self.yes {
+                    return self.delete_all_branches(selected_branches, targets);
+                }
+
+                let all = self
+                    .localization
+                    .get_message("choice-delete-all")
+                    .chars()
+                    .next()
+                    .unwrap();
+                let selected = self
+                    .localization
+                    .get_message("choice-delete-selected")
+                    .chars()
+                    .next()
+                    .unwrap();
+
+                let user_choice = self.read_user_input(
+                    self.localization.get_message("delete-selection") + " ",
+                    'n',
+                )?;
+
+                if user_choice == all {
+                    self.delete_all_branches(selected_branches, targets)?;
+                } else if user_choice == selected {
+                    self.ask_delete_all_branches(selected_branches, targets)?;
+                } else {
+                    println!("{}", self.localization.get_message("no-branch-deleted"));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn delete_all_branches(&self, branches: Vec<Branch>, targets: &[String]) -> Result<(), Error> {
+        println!();
+        for branch in &branches {
+            self.delete_branch(branch, targets)?;
+        }
+
+        Ok(())
+    }
+
+    fn ask_delete_all_branches(
+        &self,
+        branches: Vec<Branch>,
+        targets: &[String],
+    ) -> Result<(), Error> {
+        println!();
+
+        let yes = self
+            .localization
+            .get_message("choice-yes")
+            .chars()
+            .next()
+            .unwrap();
+
+        for branch in &branches {
+            let message = if branch.remote.is_some() {
+                self.localization.get_message_with_one_arg(
+                    "delete-remote-branch-yes-no",
+                    String::from("branch"),
+                    branch.name.bold().to_string(),
+                )
+            } else if branch.protected {
+                self.localization.get_message_with_one_arg(
+                    "delete-protected-branch-yes-no",
+                    String::from("branch"),
+                    branch.name.bold().to_string(),
+                )
+            } else {
+                self.localization.get_message_with_one_arg(
+                    "delete-branch-yes-no",
+                    String::from("branch"),
+                    branch.name.bold().to_string(),
+                )
+            };
+
+            if self.read_user_input(message + " ", 'n')? == yes {
+                self.delete_branch(branch, targets)?;
+            } else {
+                println!(
+                    "{}",
+                    self.localization.get_message_with_one_arg(
+                        "branch-has-not-been-deleted",
+                        String::from("branch"),
+                        branch.name.bold().to_string(),
+                    )
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether a branch is proposed for deletion: selected, and either not protected or with
+    /// protected branches included.
+    fn is_proposed(&self, branch: &Branch) -> bool {
+        (self.include_protected_branches || !branch.protected) && self.is_selected(branch)
+    }
+
+    fn is_selected(&self, branch: &Branch) -> bool {
+        self.select.is_empty()
+            || self
+                .select
+                .iter()
+                .any(|pattern| pattern.is_match(&branch.name))
+    }
+
+    /// Deletes a branch and prints the outcome. The worktree in which the branch is checked out, if
+    /// any, is only removed once confirmed, unless every deletion is.
+    ///
+    /// `targets` are the target branches the commits of a branch which is not fully merged are
+    /// checked against, before offering to force delete it.
+    fn delete_branch(&self, branch: &Branch, targets: &[String]) -> Result<(), Error> {
+        if let Some(worktree) = &branch.worktree {
+            // A backend which cannot remove worktrees keeps the branch, as told by its outcome.
+            let clean = match self.broom.is_worktree_clean(worktree) {
+                Err(Error::Unsupported) => None,
+                clean => Some(clean?),
+            };
+
+            if clean == Some(false) {
+                self.count_failure(&DeletionStatus::WorktreeNotClean);
+                self.print_worktree_not_clean(branch, worktree);
+                self.print_branch_cannot_be_deleted(branch);
+                return Ok(());
+            }
+
+            if clean.is_some() && !self.yes && !self.confirm_remove_worktree(branch, worktree)? {
+                self.print_branch_cannot_be_deleted(branch);
+                return Ok(());
+            }
+        }
+
+        for mut outcome in self.broom.apply(slice::from_ref(branch))? {
+            self.print_outcome(&outcome);
+
+            if self.force_unmerged && is_not_fully_merged(&outcome.status) {
+                if let Some(forced) = self.offer_force_delete(&outcome.branch, targets)? {
+                    outcome = forced;
+                }
+            }
+
+            self.count_failure(&outcome.status);
+        }
+
+        Ok(())
+    }
+
+    /// Lists the commits of a branch which would be lost, and force deletes it once its name is
+    /// typed. Returns the outcome of the forced deletion, `None` if it was not confirmed.
+    fn offer_force_delete(
+        &self,
+        branch: &Branch,
+        targets: &[String],
+    ) -> Result<Option<DeletionOutcome>, Error> {
+        let commits = self.broom.unmerged_commits(branch, targets)?;
+
+        println!(
+            "{}",
+            self.localization.get_message_with_count_and_one_arg(
+                "unmerged-commits",
+                commits.len(),
+                String::from("branch"),
+                branch.name.bold().to_string(),
+            )
+        );
+
+        for commit in &commits {
+            println!(
+                "  {} {}",
+                commit.id.chars().take(7).collect::<String>().yellow(),
+                commit.summary
+            );
+        }
+
+        let message = self.localization.get_message_with_one_arg(
+            "force-delete-type-name",
+            String::from("branch"),
+            branch.name.bold().to_string(),
+        );
+
+        if self.read_user_line(message + " ")? != branch.name {
+            println!(
+                "{}",
+                self.localization.get_message_with_one_arg(
+                    "branch-has-not-been-deleted",
+                    String::from("branch"),
+                    branch.name.bold().to_string(),
+                )
+            );
+            return Ok(None);
+        }
+
+        let outcome = self.broom.force_delete(branch)?;
+        self.print_outcome(&outcome);
+
+        Ok(Some(outcome))
+    }
+
+    fn count_failure(&self, status: &DeletionStatus) {
+        if matches!(
+            status,
+            DeletionStatus::Failed(_) | DeletionStatus::WorktreeNotClean | DeletionStatus::Error(_)
+        ) {
+            self.failed.set(self.failed.get() + 1);
+        }
+    }
+
+    fn print_outcome(&self, outcome: &DeletionOutcome) {
+        if let Some(worktree) = &outcome.removed_worktree {
+            println!(
+                "{}",
+                self.localization.get_message_with_one_arg(
+                    "worktree-removed",
+                    String::from("worktree"),
+                    worktree.display().to_string().bold().to_string(),
+                )
+            );
+        }
+
+        match &outcome.status {
+            DeletionStatus::Deleted => {
+                println!(
+                    "{}",
+                    self.localization.get_message_with_one_arg(
+                        "branch-deleted",
+                        String::from("branch"),
+                        outcome.branch.name.bold().to_string(),
+                    )
+                );
+
+                if let Some(reference) = &outcome.archived {
+                    println!(
+                        "  {}",
+                        self.localization
+                            .get_message_with_one_arg(
+                                "branch-archived",
+                                String::from("reference"),
+                                reference.to_string(),
+                            )
+                            .dimmed()
+                    );
+                }
+            }
+            DeletionStatus::WorktreeNotClean => {
+                if let Some(worktree) = &outcome.branch.worktree {
+                    self.print_worktree_not_clean(&outcome.branch, worktree);
+                }
+                self.print_branch_cannot_be_deleted(&outcome.branch);
+            }
+            DeletionStatus::Failed(DeletionError { message, .. })
+            | DeletionStatus::Error(message) => {
+                self.print_branch_cannot_be_deleted(&outcome.branch);
+
+                for line in message.lines() {
+                    println!("  {}", line.dimmed());
+                }
+            }
+            DeletionStatus::Protected => self.print_branch_cannot_be_deleted(&outcome.branch),
+        }
+    }
+
+    fn print_branch_cannot_be_deleted(&self, branch: &Branch) {
+        println!(
+            "{}",
+            self.localization.get_message_with_one_arg(
+                "branch-cannot-be-deleted",
+                String::from("branch"),
+                branch.name.bold().to_string(),
+            )
+        );
+    }
+
+    fn print_worktree_not_clean(&self, branch: &Branch, worktree: &Path) {
+        println!(
+            "{}",
+            self.localization.get_message_with_two_args(
+                "worktree-not-clean",
+                (String::from("branch"), branch.name.bold().to_string()),
+                (
+                    String::from("worktree"),
+                    worktree.display().to_string().bold().to_string()
+                ),
+            )
+        );
+    }
+
+    /// Asks whether the worktree in which a branch is checked out can be removed, so that the
+    /// branch can be deleted.
+    fn confirm_remove_worktree(&self, branch: &Branch, worktree: &Path) -> Result<bool, Error> {
+        let yes = self
+            .localization
+            .get_message("choice-yes")
+            .chars()
+            .next()
+            .unwrap();
+
+        let message = self.localization.get_message_with_two_args(
+            "remove-worktree-yes-no",
+            (String::from("branch"), branch.name.bold().to_string()),
+            (
+                String::from("worktree"),
+                worktree.display().to_string().bold().to_string(),
+            ),
+        );
+
+        Ok(self.read_user_input(message + " ", 'n')? == yes)
+    }
+
+    /// Returns the labels printed after a branch name, telling how it was found merged and, when
+    /// several target branches are checked, on which ones.
+    fn get_branch_labels(&self, branch: &Branch, targets: &[String]) -> String {
+        let mut labels = self.get_status_label(branch);
+
+        if let Some(worktree) = &branch.worktree {
+            labels.push_str(&format!(
+                " {}",
+                self.localization
+                    .get_message_with_one_arg(
+                        "checked-out-in-worktree",
+                        String::from("worktree"),
+                        worktree.display().to_string(),
+                    )
+                    .yellow()
+            ));
+        }
+
+        if targets.len() > 1 && !branch.targets.is_empty() {
+            labels.push_str(&format!(
+                " {}",
+                self.localization
+                    .get_message_with_one_arg(
+                        "merged-into",
+                        String::from("targets"),
+                        branch.targets.join(", "),
+                    )
+                    .dimmed()
+            ));
+        }
+
+        labels
+    }
+
+    fn get_status_label(&self, branch: &Branch) -> String {
+        match branch.status {
+            MergeStatus::Merged => String::new(),
+            MergeStatus::SquashMerged => {
+                format!(" {}", self.localization.get_message("squash-merged").cyan())
+            }
+            MergeStatus::RebaseMerged(commits) => format!(
+                " {}",
+                self.localization
+                    .get_message_with_count("rebase-merged", commits)
+                    .cyan()
+            ),
+            MergeStatus::UpstreamGone => {
+                format!(" {}", self.localization.get_message("upstream-gone").cyan())
+            }
+            MergeStatus::RemoteRemoved => {
+                format!(
+                    " {}",
+                    self.localization.get_message("remote-removed").cyan()
+                )
+            }
+            MergeStatus::StaleRemoteBranch => {
+                format!(" {}", self.localization.get_message("not-on-remote").cyan())
+            }
+        }
+    }
+
+    /// Restores the last deleted branch with this name, and prints it.
+    pub fn restore(&self, branch: &str) -> Result<(), Error> {
+        let entry = self.broom.restore(branch)?;
+        self.print_restored(&entry);
+
+        Ok(())
+    }
+
+    /// Restores the branches deleted by the last run, and prints them.
+    pub fn undo(&self) -> Result<(), Error> {
+        for entry in self.broom.undo()? {
+            self.print_restored(&entry);
+        }
+
+        Ok(())
+    }
+
+    fn print_restored(&self, entry: &JournalEntry) {
+        println!(
+            "{}",
+            self.localization.get_message_with_two_args(
+                "branch-restored",
+                (String::from("branch"), entry.name.bold().to_string()),
+                (
+                    String::from("tip"),
+                    entry
+                        .tip
+                        .chars()
+                        .take(7)
+                        .collect::<String>()
+                        .yellow()
+                        .to_string()
+                ),
+            )
+        );
+
+        if let (Some(remote), Some(merge)) = (&entry.remote, &entry.merge) {
+            println!(
+                "  {}",
+                self.localization
+                    .get_message_with_two_args(
+                        "upstream-restored",
+                        (String::from("remote"), remote.clone()),
+                        (String::from("merge"), merge.clone()),
+                    )
+                    .dimmed()
+            );
+        }
+    }
+
+    /// Prints the archived branches, along with their tip and the day they were deleted.
+    pub fn list_archive(&self) -> Result<(), Error> {
+        let archived_branches = self.broom.archived_branches()?;
+
+        if archived_branches.is_empty() {
+            println!("{}", self.localization.get_message("no-archived-branch"));
+            return Ok(());
+        }
+
+        println!(
+            "{}",
+            self.localization
+                .get_message_with_count("archived-branches", archived_branches.len())
+        );
+
+        for archived in &archived_branches {
+            self.print_archived_branch(archived);
+        }
+
+        Ok(())
+    }
+
+    /// Deletes the archived branches older than `retention_days`, and prints them.
+    pub fn purge_archive(&self, retention_days: u64) -> Result<(), Error> {
+        let purged = self.broom.purge_archive(retention_days)?;
+
+        if purged.is_empty() {
+            println!(
+                "{}",
+                self.localization.get_message_with_one_arg(
+                    "no-archive-purged",
+                    String::from("days"),
+                    retention_days.to_string(),
+                )
+            );
+            return Ok(());
+        }
+
+        println!(
+            "{}",
+            self.localization.get_message_with_count_and_one_arg(
+                "archive-purged",
+                purged.len(),
+                String::from("days"),
+                retention_days.to_string(),
+            )
+        );
+
+        for archived in &purged {
+            self.print_archived_branch(archived);
+        }
+
+        Ok(())
+    }
+
+    fn print_archived_branch(&self, archived: &ArchivedBranch) {
+        println!(
+            "  * {} {} {}",
+            archived.date.dimmed(),
+            if archived.remote {
+                format!("remotes/{}", archived.name).blue()
+            } else {
+                archived.name.blue()
+            },
+            archived.tip.chars().take(7).collect::<String>().yellow()
+        );
+    }
+
+    /// Prints the protection rules along with the configuration file each one comes from.
+    pub fn show_protection(&self) -> Result<(), Error> {
+        let rules = self.broom.protection_rules()?;
+
+        for key in ["broom.protectedbranches", "broom.protectedremotebranches"] {
+            let rules: Vec<_> = rules.iter().filter(|rule| rule.key.eq(key)).collect();
+
+            if rules.is_empty() {
+                println!(
+                    "{}",
+                    self.localization.get_message_with_one_arg(
+                        "no-protection-rule",
+                        String::from("key"),
+                        key.bold().to_string(),
+                    )
+                );
+            } else {
+                println!(
+                    "{}",
+                    self.localization.get_message_with_count_and_one_arg(
+                        "protection-rules",
+                        rules.len(),
+                        String::from("key"),
+                        key.bold().to_string(),
+                    )
+                );
+
+                for rule in rules {
+                    println!(
+                        "  * {} {}",
+                        rule.pattern.as_str().blue(),
+                        self.localization
+                            .get_message_with_one_arg(
+                                "rule-origin",
+                                String::from("origin"),
+                                rule.origin.clone(),
+                            )
+                            .dimmed()
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Asks a question and returns the first letter of the answer, or `default` if there is none.
+    /// Fails rather than reading answers which were not typed when the standard input is not a
+    /// terminal.
+    fn read_user_input(&self, message: String, default: char) -> Result<char, Error> {
+        let choice = self.read_user_line(message)?;
+
+        if choice.len() == 1 {
+            Ok(choice.to_lowercase().chars().next().unwrap())
+        } else {
+            Ok(default)
+        }
+    }
+
+    /// Asks a question and returns the trimmed answer, failing when the standard input is not a
+    /// terminal.
+    fn read_user_line(&self, message: String) -> Result<String, Error> {
+        if !io::stdin().is_terminal() {
+            return Err(Error::Io(io::Error::other(
+                self.localization.get_message("stdin-not-a-terminal"),
+            )));
+        }
+
+        print!("{}", message);
+        io::stdout().flush().unwrap();
+
+        let mut answer = String::new();
+
+        io::stdin().read_line(&mut answer)?;
+
+        Ok(answer.trim().to_string())
+    }
+}
+
+fn is_not_fully_merged(status: &DeletionStatus) -> bool {
+    matches!(status, DeletionStatus::Failed(e) if e.kind == DeletionErrorKind::NotFullyMerged)
+}
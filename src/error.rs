@@ -0,0 +1,228 @@
+/*
+Git Broom
+Copyright (C) 2024  All contributors.
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use std::path::PathBuf;
+use std::{error, fmt, io};
+
+use crate::i18n::Localization;
+
+/// Why Git Broom failed. The messages are localized.
+#[derive(Debug)]
+pub enum Error {
+    /// The `git` command cannot be found.
+    GitNotFound,
+    NotARepository,
+    /// None of the target branches given exists.
+    NoTargetBranch,
+    /// The default branch was asked for, but cannot be resolved from the remotes nor from
+    /// `init.defaultBranch`.
+    NoDefaultBranch,
+    /// Target branches given by name do not exist.
+    NoSuchTarget {
+        targets: Vec<String>,
+    },
+    /// A target branch pattern, starting with `^`, is not a valid regular expression.
+    InvalidTargetPattern {
+        pattern: String,
+        source: regex::Error,
+    },
+    NoSuchRemote {
+        remote: String,
+    },
+    /// A protection rule is not a valid regular expression. `origin` is the configuration file it
+    /// comes from.
+    InvalidProtectionRule {
+        key: String,
+        pattern: String,
+        origin: String,
+        source: regex::Error,
+    },
+    /// The Git configuration cannot be read, so the protection rules are unknown.
+    Config(Box<dyn error::Error + Send + Sync>),
+    /// The operation is not supported by the backend.
+    Unsupported,
+    /// Git refused to delete a branch or to remove a worktree.
+    Deletion(DeletionError),
+    /// The journal holds no deletion of a branch to restore.
+    NotInJournal {
+        branch: String,
+    },
+    /// The journal holds no deletion to undo.
+    NothingToUndo,
+    /// A branch cannot be restored, as a branch with the same name exists.
+    BranchExists {
+        branch: String,
+    },
+    /// A branch cannot be restored, as the commit it pointed to was garbage collected.
+    CommitNotFound {
+        branch: String,
+        tip: String,
+    },
+    /// The branches about to be deleted cannot be saved to a bundle, so none was deleted.
+    Bundle {
+        path: PathBuf,
+        source: Box<Error>,
+    },
+    /// The backend failed to read or write the repository.
+    Backend(Box<dyn error::Error + Send + Sync>),
+    Io(io::Error),
+}
+
+impl Error {
+    /// Wraps an error of the backend.
+    pub fn backend(error: impl Into<Box<dyn error::Error + Send + Sync>>) -> Self {
+        Self::Backend(error.into())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let localization = Localization::new();
+
+        let message = match self {
+            Self::GitNotFound => localization.get_message("git-not-found"),
+            Self::NotARepository => localization.get_message("not-a-git-repository"),
+            Self::NoTargetBranch => localization.get_message("no-valid-branch-found"),
+            Self::NoDefaultBranch => localization.get_message("no-default-branch-found"),
+            Self::NoSuchTarget { targets } => localization.get_message_with_one_arg(
+                "no-such-target",
+                String::from("targets"),
+                targets.join(", "),
+            ),
+            Self::InvalidTargetPattern { pattern, .. } => localization.get_message_with_one_arg(
+                "invalid-target-pattern",
+                String::from("pattern"),
+                pattern.clone(),
+            ),
+            Self::NoSuchRemote { remote } => localization.get_message_with_one_arg(
+                "no-such-remote",
+                String::from("remote"),
+                remote.clone(),
+            ),
+            Self::InvalidProtectionRule {
+                key,
+                pattern,
+                origin,
+                ..
+            } => localization.get_message_with_two_args(
+                "invalid-protected-pattern",
+                (String::from("pattern"), pattern.clone()),
+                (String::from("key"), format!("{} ({})", key, origin)),
+            ),
+            Self::Config(e) => localization.get_message_with_one_arg(
+                "config-cannot-be-read",
+                String::from("error"),
+                e.to_string(),
+            ),
+            Self::Unsupported => localization.get_message("unsupported-by-backend"),
+            Self::Deletion(e) => e.to_string(),
+            Self::NotInJournal { branch } => localization.get_message_with_one_arg(
+                "not-in-journal",
+                String::from("branch"),
+                branch.clone(),
+            ),
+            Self::NothingToUndo => localization.get_message("nothing-to-undo"),
+            Self::BranchExists { branch } => localization.get_message_with_one_arg(
+                "branch-already-exists",
+                String::from("branch"),
+                branch.clone(),
+            ),
+            Self::CommitNotFound { branch, tip } => localization.get_message_with_two_args(
+                "commit-not-found",
+                (String::from("branch"), branch.clone()),
+                (String::from("tip"), tip.clone()),
+            ),
+            Self::Bundle { path, source } => localization.get_message_with_two_args(
+                "bundle-failed",
+                (String::from("path"), path.display().to_string()),
+                (String::from("error"), source.to_string()),
+            ),
+            Self::Backend(e) => e.to_string(),
+            Self::Io(e) => e.to_string(),
+        };
+
+        f.write_str(&message)
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::InvalidProtectionRule { source, .. }
+            | Self::InvalidTargetPattern { source, .. } => Some(source),
+            Self::Config(e) | Self::Backend(e) => Some(e.as_ref()),
+            Self::Deletion(e) => Some(e),
+            Self::Bundle { source, .. } => Some(source.as_ref()),
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<DeletionError> for Error {
+    fn from(error: DeletionError) -> Self {
+        Self::Deletion(error)
+    }
+}
+
+/// Why Git refused to delete a branch, along with its diagnostic.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeletionError {
+    pub kind: DeletionErrorKind,
+    /// The diagnostic of Git, such as `error: the branch 'feature' is not fully merged`.
+    pub message: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeletionErrorKind {
+    /// The branch is not merged on its upstream nor on `HEAD`, and was not force deleted.
+    NotFullyMerged,
+    /// The branch is checked out in a worktree.
+    CheckedOut,
+    /// The reference is locked by another Git process.
+    RefLocked,
+    /// The branch no longer exists.
+    NotFound,
+    /// The remote refused to delete the branch, such as when it was updated since it was last
+    /// fetched.
+    RemoteRejected,
+    Other,
+}
+
+impl DeletionError {
+    pub fn new(kind: DeletionErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for DeletionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl error::Error for DeletionError {}
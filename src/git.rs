@@ -16,356 +16,1008 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <http://www.gnu.org/licenses/>.
 */
 
-use std::io::{ErrorKind, Write};
+use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::Command;
-use std::{env, io};
+use std::sync::Mutex;
+use std::{env, error};
 
-use colored::*;
+use gix::bstr::BStr;
 use regex::Regex;
 
-use crate::i18n::Localization;
+use crate::archive::{archive_reference, ArchivedBranch, ARCHIVE_PREFIX};
+use crate::backend::{Backend, CommandBackend};
+use crate::branch::{Branch, Commit, MergeStatus, ProtectionRule};
+use crate::error::{DeletionError, DeletionErrorKind, Error};
+use crate::journal::{self, JournalEntry};
+use crate::report::{Bundle, DeletionOutcome, DeletionStatus, Notice, Report};
 
+const PROTECTED_BRANCHES_KEY: &str = "broom.protectedbranches";
+const PROTECTED_REMOTE_BRANCHES_KEY: &str = "broom.protectedremotebranches";
+
+/// Looks for the branches of a repository which can be deleted, and deletes them.
+///
+/// Nothing is printed: [`GitBroom::scan`] returns a [`Report`], and [`GitBroom::apply`] deletes the
+/// chosen branches and returns what happened to each of them.
 pub struct GitBroom {
-    repository: Option<String>,
-    branch: Option<String>,
-    dry_run: bool,
+    options: BroomOptions,
+    backend: Box<dyn Backend>,
+    /// Identifies the deletions of this instance in the journal.
+    run: String,
+    /// The bundle the branches were last saved to, referenced by the journal.
+    bundle: Mutex<Option<String>>,
+}
+
+#[derive(Clone, Default)]
+struct BroomOptions {
+    /// Path of the Git repository, the current path if `None`.
+    repository: Option<PathBuf>,
+    /// Branches, or regular expressions, to check if local branches are merged on.
+    branches: Vec<String>,
+    /// Use the default branch of the repository as target branch.
+    default_target: bool,
     include_protected_branches: bool,
-    current_dir: Option<PathBuf>,
-    localization: Localization,
+    squash_merged: bool,
+    rebase_merged: bool,
+    /// Also look for branches whose upstream branch was deleted from the remote.
+    gone: bool,
+    /// Fetch and prune the relevant remotes before looking for merged branches.
+    fetch: bool,
+    /// Look for the branches of this remote instead of the local branches.
+    remote: Option<String>,
+    /// Also look for remote-tracking branches of removed remotes, or which no longer exist on their
+    /// remote.
+    stale: bool,
+    /// Check against the upstream of the target branches instead of their local copy.
+    upstream: bool,
+    /// Record the tip of the deleted branches in the archive, as configured with `broom.archive`
+    /// if `None`.
+    archive: Option<bool>,
 }
 
-#[derive(Clone)]
-struct Branch {
-    name: String,
-    protected: bool,
+/// Builds a [`GitBroom`]. Only merged local branches are looked for by default, on the current
+/// branch of the repository in the current path.
+#[derive(Default)]
+pub struct GitBroomBuilder {
+    options: BroomOptions,
+    backend: Option<Box<dyn Backend>>,
 }
 
-impl GitBroom {
-    pub fn new(
-        repository: Option<String>,
-        branch: Option<String>,
-        dry_run: bool,
-        include_protected_branches: bool,
-    ) -> Self {
-        Self {
-            repository,
-            branch,
-            dry_run,
-            include_protected_branches,
-            current_dir: {
-                match env::current_dir() {
-                    Ok(path) => Some(path),
-                    Err(_) => None,
-                }
-            },
-            localization: Localization::new(),
+impl GitBroomBuilder {
+    /// Sets the path of the Git repository, for the default backend.
+    pub fn repository(mut self, repository: impl Into<PathBuf>) -> Self {
+        self.options.repository = Some(repository.into());
+        self
+    }
+
+    /// Sets the backend every Git operation goes through, a [`CommandBackend`] running the `git`
+    /// command by default.
+    pub fn backend(mut self, backend: impl Backend + 'static) -> Self {
+        self.backend = Some(Box::new(backend));
+        self
+    }
+
+    /// Adds a branch to check if branches are merged on, or a regular expression starting with `^`
+    /// matched against the whole local branch names.
+    pub fn branch(mut self, branch: impl Into<String>) -> Self {
+        self.options.branches.push(branch.into());
+        self
+    }
+
+    /// Adds several branches, or regular expressions starting with `^`, to check if branches are
+    /// merged on.
+    pub fn branches<I, S>(mut self, branches: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.options
+            .branches
+            .extend(branches.into_iter().map(Into::into));
+        self
+    }
+
+    /// Uses the default branch of the repository, resolved from its remotes, when no branch is
+    /// given.
+    pub fn default_target(mut self, default_target: bool) -> Self {
+        self.options.default_target = default_target;
+        self
+    }
+
+    /// Lets [`GitBroom::apply`] delete protected branches.
+    pub fn include_protected_branches(mut self, include_protected_branches: bool) -> Self {
+        self.options.include_protected_branches = include_protected_branches;
+        self
+    }
+
+    /// Also detects branches whose changes exist as a single commit on a target branch.
+    pub fn squash_merged(mut self, squash_merged: bool) -> Self {
+        self.options.squash_merged = squash_merged;
+        self
+    }
+
+    /// Also detects branches whose every commit has an equivalent commit on a target branch.
+    pub fn rebase_merged(mut self, rebase_merged: bool) -> Self {
+        self.options.rebase_merged = rebase_merged;
+        self
+    }
+
+    /// Checks against the upstream of the target branches instead of their local copy.
+    pub fn upstream(mut self, upstream: bool) -> Self {
+        self.options.upstream = upstream;
+        self
+    }
+
+    /// Also looks for branches whose upstream branch was deleted from the remote.
+    pub fn gone(mut self, gone: bool) -> Self {
+        self.options.gone = gone;
+        self
+    }
+
+    /// Fetches and prunes the relevant remotes before looking for branches. This is also enabled
+    /// by the `broom.fetch` configuration.
+    pub fn fetch(mut self, fetch: bool) -> Self {
+        self.options.fetch = fetch;
+        self
+    }
+
+    /// Looks for the branches of a remote instead of the local branches.
+    pub fn remote(mut self, remote: impl Into<String>) -> Self {
+        self.options.remote = Some(remote.into());
+        self
+    }
+
+    /// Also looks for remote-tracking branches of removed remotes, or which no longer exist on
+    /// their remote.
+    pub fn stale(mut self, stale: bool) -> Self {
+        self.options.stale = stale;
+        self
+    }
+
+    /// Records the tip of each deleted branch under `refs/broom/archive/<date>/<id>/<ref>`, so
+    /// that it can be restored. This is enabled by default, unless disabled by the `broom.archive`
+    /// configuration.
+    pub fn archive(mut self, archive: bool) -> Self {
+        self.options.archive = Some(archive);
+        self
+    }
+
+    pub fn build(self) -> GitBroom {
+        let backend = self
+            .backend
+            .unwrap_or_else(|| Box::new(CommandBackend::new(self.options.repository.clone())));
+
+        GitBroom {
+            options: self.options,
+            backend,
+            run: journal::run_id(),
+            bundle: Mutex::new(None),
         }
     }
+}
+
+impl GitBroom {
+    pub fn builder() -> GitBroomBuilder {
+        GitBroomBuilder::default()
+    }
+
+    /// Looks for the branches which can be deleted, without deleting anything.
+    pub fn scan(&self) -> Result<Report, Error> {
+        self.scan_repository()
+    }
+
+    /// Deletes branches, usually chosen among the ones of a [`Report`], and tells what happened to
+    /// each of them.
+    ///
+    /// Protected branches are refused unless they are included. A branch checked out in a linked
+    /// worktree is deleted along with the worktree, provided it has no local changes.
+    ///
+    /// Only fails when the repository cannot be worked on. Once branches are being deleted, there
+    /// is one outcome per branch, whatever happens to the others.
+    pub fn apply(&self, branches: &[Branch]) -> Result<Vec<DeletionOutcome>, Error> {
+        self.check_repository()?;
+
+        Ok(branches
+            .iter()
+            .map(|branch| self.delete_branch(branch, false))
+            .collect())
+    }
+
+    /// Force deletes a local branch which a safe delete refused, losing the commits returned by
+    /// [`GitBroom::unmerged_commits`].
+    ///
+    /// Protected branches are refused unless they are included, like with [`GitBroom::apply`].
+    pub fn force_delete(&self, branch: &Branch) -> Result<DeletionOutcome, Error> {
+        self.check_repository()?;
+
+        Ok(self.delete_branch(branch, true))
+    }
+
+    /// Returns the commits of a local branch which are on none of `targets`, usually the target
+    /// branches of the [`Report`] it comes from, newest first.
+    pub fn unmerged_commits(
+        &self,
+        branch: &Branch,
+        targets: &[String],
+    ) -> Result<Vec<Commit>, Error> {
+        self.backend.unmerged_commits(&branch.ref_name(), targets)
+    }
+
+    /// Returns the deleted branches kept in the archive, oldest first.
+    pub fn archived_branches(&self) -> Result<Vec<ArchivedBranch>, Error> {
+        self.check_repository()?;
+
+        Ok(self
+            .backend
+            .references(ARCHIVE_PREFIX)?
+            .into_iter()
+            .filter_map(|(reference, tip)| ArchivedBranch::from_reference(reference, tip))
+            .collect())
+    }
+
+    /// Deletes the archived branches archived more than `retention_days` days ago, and returns
+    /// them.
+    pub fn purge_archive(&self, retention_days: u64) -> Result<Vec<ArchivedBranch>, Error> {
+        let mut purged: Vec<ArchivedBranch> = Vec::new();
 
-    pub fn broom(&self) -> Result<(), io::Error> {
-        if let Some(repository) = &self.repository {
-            env::set_current_dir(Path::new(repository))?;
+        for archived in self.archived_branches()? {
+            if archived.age_in_days() > retention_days {
+                self.backend.delete_reference(&archived.reference)?;
+                purged.push(archived);
+            }
         }
 
-        if self.check_git()? && self.check_repository()? {
-            self.broom_branch(self.get_working_branch()?)?;
+        Ok(purged)
+    }
+
+    /// Saves branches, usually the ones about to be given to [`GitBroom::apply`], to a bundle file
+    /// along with their history, then verifies it. The journal entries of the branches deleted
+    /// afterwards reference the bundle.
+    ///
+    /// Fails with [`Error::Bundle`] if the bundle cannot be written or verified, in which case
+    /// nothing should be deleted.
+    pub fn bundle(&self, path: impl AsRef<Path>, branches: &[Branch]) -> Result<Bundle, Error> {
+        let path = env::current_dir()?.join(path);
+        let references: Vec<String> = branches.iter().map(Branch::ref_name).collect();
+
+        self.backend
+            .create_bundle(&path, &references)
+            .and_then(|_| self.backend.verify_bundle(&path))
+            .map_err(|e| Error::Bundle {
+                path: path.clone(),
+                source: Box::new(e),
+            })?;
+
+        let path = fs::canonicalize(&path).unwrap_or(path);
+        *self.bundle.lock().unwrap() = Some(path.to_string_lossy().into_owned());
+
+        Ok(Bundle { path, references })
+    }
+
+    /// Recreates the last deleted branch with this name, along with its upstream configuration, and
+    /// removes it from the journal.
+    pub fn restore(&self, branch: &str) -> Result<JournalEntry, Error> {
+        let path = self.get_journal_path()?;
+        let mut entries = journal::read(&path)?;
+
+        let index = entries
+            .iter()
+            .rposition(|entry| entry.name == branch)
+            .ok_or_else(|| Error::NotInJournal {
+                branch: branch.to_string(),
+            })?;
+
+        self.restore_entry(&entries[index])?;
+        let entry = entries.remove(index);
+        journal::write(&path, &entries)?;
+
+        Ok(entry)
+    }
+
+    /// Recreates every branch deleted by the last run found in the journal, and removes them from
+    /// the journal. Nothing is restored if any of them cannot be.
+    pub fn undo(&self) -> Result<Vec<JournalEntry>, Error> {
+        let path = self.get_journal_path()?;
+        let entries = journal::read(&path)?;
+
+        let run = entries.last().ok_or(Error::NothingToUndo)?.run.clone();
+        let (restored, kept): (Vec<JournalEntry>, Vec<JournalEntry>) =
+            entries.into_iter().partition(|entry| entry.run == run);
+
+        for entry in &restored {
+            self.check_restorable(entry)?;
         }
 
-        if let Some(path) = &self.current_dir {
-            env::set_current_dir(path)?;
+        for entry in &restored {
+            self.restore_entry(entry)?;
         }
 
-        Ok(())
+        journal::write(&path, &kept)?;
+
+        Ok(restored)
     }
 
-    fn check_git(&self) -> Result<bool, io::Error> {
-        let output_result = Command::new("git").arg("--version").output();
-        if let Ok(output) = output_result {
-            if let Some(code) = output.status.code() {
-                if code == 0 {
-                    return Ok(true);
-                }
-            }
-        }
+    /// Returns the rules protecting the local branches, then the ones protecting the remote
+    /// branches, along with the configuration file each one comes from.
+    pub fn protection_rules(&self) -> Result<Vec<ProtectionRule>, Error> {
+        self.check_repository()?;
+
+        let mut rules = self.get_protection_rules(PROTECTED_BRANCHES_KEY)?;
+        rules.extend(self.get_protection_rules(PROTECTED_REMOTE_BRANCHES_KEY)?);
 
-        Err(io::Error::new(
-            ErrorKind::Other,
-            self.localization.get_message("git-not-found"),
-        ))
+        Ok(rules)
     }
 
-    fn check_repository(&self) -> Result<bool, io::Error> {
-        let output = Command::new("git").arg("status").output()?;
-        if let Some(code) = output.status.code() {
-            if code == 0 {
-                return Ok(true);
+    /// Checks that a worktree has no local changes, so that removing it loses nothing.
+    pub fn is_worktree_clean(&self, worktree: &Path) -> Result<bool, Error> {
+        self.backend.is_worktree_clean(worktree)
+    }
+
+    fn scan_repository(&self) -> Result<Report, Error> {
+        self.check_repository()?;
+
+        let mut notices: Vec<Notice> = Vec::new();
+        let targets = self.get_target_branches(&mut notices)?;
+
+        if self.options.fetch || self.get_config_bool("broom.fetch", false)? {
+            self.fetch(&targets, &mut notices)?;
+        }
+
+        let mut report = if let Some(remote) = &self.options.remote {
+            let targets = self.get_remote_targets(remote, targets)?;
+
+            Report {
+                merged: self.get_remote_merged_branches(remote, &targets)?,
+                targets,
+                remote: Some(remote.clone()),
+                ..Default::default()
             }
+        } else {
+            let targets = if self.options.upstream {
+                self.get_upstream_branches(targets, &mut notices)?
+            } else {
+                self.warn_behind_upstream(&targets, &mut notices)?;
+                targets
+            };
+
+            let merged = self.get_merged_branches(&targets)?;
+            let gone = if self.options.gone {
+                Some(self.get_gone_branches(&targets, &merged)?)
+            } else {
+                None
+            };
+
+            Report {
+                targets,
+                merged,
+                gone,
+                ..Default::default()
+            }
+        };
+
+        if self.options.stale {
+            report.stale = Some(self.get_stale_refs(&mut notices)?);
         }
 
-        Err(io::Error::new(
-            ErrorKind::Other,
-            self.localization.get_message("not-a-git-repository"),
-        ))
+        for branch in report
+            .merged
+            .iter_mut()
+            .chain(report.gone.iter_mut().flatten())
+            .chain(report.stale.iter_mut().flatten())
+        {
+            branch.tip = self.backend.resolve_commit(&branch.ref_name())?;
+        }
+
+        report.notices = notices;
+
+        Ok(report)
     }
 
-    fn broom_branch(&self, branch: String) -> Result<(), io::Error> {
-        let merged_branches = self.get_merged_branches(&branch)?;
+    /// Checks that the repository can be worked on, Git being installed when it is run.
+    fn check_repository(&self) -> Result<(), Error> {
+        self.backend.git_directories().map(|_| ())
+    }
 
-        if !merged_branches.is_empty() {
-            let protected_branches: Vec<Branch> = merged_branches
-                .iter()
-                .cloned()
-                .filter(|branch| !self.include_protected_branches && branch.protected)
-                .collect();
+    /// Deletes a branch. Unless `force_unmerged`, a local branch is only force deleted when its
+    /// changes are known to be on a target branch.
+    fn delete_branch(&self, branch: &Branch, force_unmerged: bool) -> DeletionOutcome {
+        let mut outcome = DeletionOutcome {
+            branch: branch.clone(),
+            status: DeletionStatus::Deleted,
+            removed_worktree: None,
+            archived: None,
+        };
 
-            let not_protected_branches: Vec<Branch> = merged_branches
-                .iter()
-                .cloned()
-                .filter(|branch| self.include_protected_branches || !branch.protected)
-                .collect();
-
-            if !protected_branches.is_empty() {
-                println!(
-                    "{}",
-                    self.localization.get_message_with_count_and_one_arg(
-                        "found-merged-protected",
-                        protected_branches.len(),
-                        String::from("branch"),
-                        branch.bold().underline().to_string()
-                    )
-                );
-
-                for branch in &protected_branches {
-                    println!("  * {}", branch.name.blue());
-                }
+        if let Err(e) = self.try_delete_branch(branch, force_unmerged, &mut outcome) {
+            outcome.status = DeletionStatus::Error(e.to_string());
+        }
+
+        outcome
+    }
 
-                println!(
-                    "{}",
-                    self.localization.get_message_with_count(
-                        "branches-wont-be-deleted",
-                        protected_branches.len()
-                    )
-                );
+    /// Deletes a branch, filling its outcome. Fails only while the branch still exists.
+    fn try_delete_branch(
+        &self,
+        branch: &Branch,
+        force_unmerged: bool,
+        outcome: &mut DeletionOutcome,
+    ) -> Result<(), Error> {
+        if branch.protected && !self.options.include_protected_branches {
+            outcome.status = DeletionStatus::Protected;
+            return Ok(());
+        }
 
-                if !not_protected_branches.is_empty() {
-                    println!();
-                }
+        let force = match self.needs_force_delete(branch, force_unmerged) {
+            Err(Error::Deletion(e)) => {
+                outcome.status = DeletionStatus::Failed(e);
+                return Ok(());
             }
+            force => force?,
+        };
 
-            if !not_protected_branches.is_empty() {
-                println!(
-                    "{}",
-                    self.localization.get_message_with_count_and_one_arg(
-                        "found-merged",
-                        not_protected_branches.len(),
-                        String::from("branch"),
-                        branch.bold().underline().to_string(),
-                    )
-                );
-
-                for branch in &not_protected_branches {
-                    if branch.protected {
-                        println!(
-                            "  * {} {}",
-                            branch.name.red(),
-                            self.localization.get_message("protected").red()
-                        );
-                    } else {
-                        println!("  * {}", branch.name.green());
-                    }
+        if let Some(worktree) = self.get_checked_out_worktree(branch)? {
+            // The worktree is only removed when the safe delete which follows will go through.
+            if !force && !self.is_merged_on_upstream_or_head(&branch.name)? {
+                outcome.status = DeletionStatus::Failed(not_fully_merged_error(branch));
+                return Ok(());
+            }
+
+            // A backend which cannot remove worktrees keeps the branch, like Git would.
+            let clean = match self.backend.is_worktree_clean(&worktree) {
+                Err(Error::Unsupported) => {
+                    outcome.status = DeletionStatus::Failed(checked_out_error(branch, &worktree));
+                    return Ok(());
                 }
+                clean => clean?,
+            };
+            if !clean {
+                outcome.status = DeletionStatus::WorktreeNotClean;
+                return Ok(());
+            }
 
-                if !self.dry_run {
-                    let all = self
-                        .localization
-                        .get_message("choice-delete-all")
-                        .chars()
-                        .next()
-                        .unwrap();
-                    let selected = self
-                        .localization
-                        .get_message("choice-delete-selected")
-                        .chars()
-                        .next()
-                        .unwrap();
-
-                    let user_choice_result = self.read_user_input(
-                        self.localization.get_message("delete-selection") + " ",
-                        'n',
-                    );
-
-                    if user_choice_result.is_ok() {
-                        let user_choice = user_choice_result.unwrap();
-                        if user_choice == all {
-                            self.delete_all_branches(not_protected_branches)?;
-                        } else if user_choice == selected {
-                            self.ask_delete_all_branches(not_protected_branches)?;
-                        } else {
-                            println!("{}", self.localization.get_message("no-branch-deleted"));
-                        }
-                    } else {
-                        println!("{}", self.localization.get_message("no-branch-deleted"));
-                    }
+            outcome.status = match self.backend.remove_worktree(&worktree) {
+                Err(Error::Unsupported) => {
+                    DeletionStatus::Failed(checked_out_error(branch, &worktree))
                 }
+                removed => deletion_status(removed)?,
+            };
+            if outcome.status != DeletionStatus::Deleted {
+                return Ok(());
             }
+
+            outcome.removed_worktree = Some(worktree);
+        }
+
+        let archived = self.archive_branch(branch)?;
+        let status = self.delete_journaled_branch(branch, force);
+
+        if matches!(status, Ok(DeletionStatus::Deleted)) {
+            outcome.archived = archived;
+        } else if let Some(reference) = archived {
+            // The branch still exists. An archive reference left behind is only purged later.
+            let _ = self.backend.delete_reference(&reference);
+        }
+
+        outcome.status = status?;
+
+        Ok(())
+    }
+
+    /// Journals a local branch, then deletes the branch, forcing the deletion of a local branch
+    /// if `force`. The journal entry is removed if the branch cannot be deleted, so that the
+    /// journal only holds deleted branches.
+    fn delete_journaled_branch(
+        &self,
+        branch: &Branch,
+        force: bool,
+    ) -> Result<DeletionStatus, Error> {
+        let journal_path = self.get_journal_path()?;
+        let journal_entry = self.get_journal_entry(branch)?;
+
+        if let Some(entry) = &journal_entry {
+            journal::append(&journal_path, entry)?;
+        }
+
+        let deleted = if let Some(remote) = &branch.remote {
+            self.delete_remote_branch(remote, branch)
+        } else if branch.is_stale_ref() {
+            self.backend.delete_remote_tracking_branch(&branch.name)
         } else {
-            println!(
-                "{}",
-                self.localization.get_message_with_one_arg(
-                    "no-merged-branch",
-                    String::from("branch"),
-                    branch.bold().to_string(),
+            self.backend.delete_branch(&branch.name, force)
+        };
+
+        let status = deletion_status(deleted);
+
+        if !matches!(status, Ok(DeletionStatus::Deleted)) {
+            if let Some(entry) = &journal_entry {
+                // A leftover entry would only be refused by `restore`, as the branch exists.
+                let _ = journal::remove(&journal_path, entry);
+            }
+        }
+
+        status
+    }
+
+    /// Tells whether a local branch is force deleted.
+    ///
+    /// A safe delete only accepts branches merged on their upstream or on HEAD. Branches merged on
+    /// another target branch, or merged by content, would be refused even though their changes are
+    /// already there. Branches whose upstream is gone may not be merged at all, so they are only
+    /// force deleted when asked to.
+    ///
+    /// A branch merged by content is refused if it changed since it was scanned, as its new
+    /// commits would be lost.
+    fn needs_force_delete(&self, branch: &Branch, force_unmerged: bool) -> Result<bool, Error> {
+        if branch.remote.is_some() || branch.is_stale_ref() {
+            return Ok(false);
+        }
+
+        Ok(force_unmerged
+            || match branch.status {
+                MergeStatus::Merged => self.is_merged_on_any(&branch.name, &branch.targets)?,
+                MergeStatus::UpstreamGone => false,
+                MergeStatus::SquashMerged | MergeStatus::RebaseMerged(_) => {
+                    self.check_unchanged(branch)?;
+                    true
+                }
+                MergeStatus::RemoteRemoved | MergeStatus::StaleRemoteBranch => true,
+            })
+    }
+
+    /// Checks that a branch still points to the tip it was scanned with.
+    fn check_unchanged(&self, branch: &Branch) -> Result<(), Error> {
+        let tip = match self.backend.resolve_commit(&branch.ref_name())? {
+            Some(tip) => tip,
+            None => {
+                return Err(DeletionError::new(
+                    DeletionErrorKind::NotFound,
+                    format!("error: branch '{}' not found", branch.name),
                 )
-            );
+                .into())
+            }
+        };
+
+        if branch.tip.as_ref() == Some(&tip) {
+            return Ok(());
+        }
+
+        Err(DeletionError::new(
+            DeletionErrorKind::NotFullyMerged,
+            format!(
+                "error: the branch '{}' changed since it was found merged",
+                branch.name
+            ),
+        )
+        .into())
+    }
+
+    /// Returns the journal entry of a local branch about to be deleted, along with its upstream
+    /// configuration which is removed with it. Remote branches and remote-tracking branches are
+    /// not journaled, as they are not restored locally.
+    fn get_journal_entry(&self, branch: &Branch) -> Result<Option<JournalEntry>, Error> {
+        if branch.remote.is_some() || branch.is_stale_ref() {
+            return Ok(None);
+        }
+
+        let tip = match self.backend.resolve_commit(&branch.ref_name())? {
+            Some(tip) => tip,
+            None => return Ok(None),
+        };
+
+        let config = self.get_config_file()?;
+        let subsection = Some(BStr::new(&branch.name));
+
+        Ok(Some(JournalEntry {
+            run: self.run.clone(),
+            timestamp: journal::now(),
+            name: branch.name.clone(),
+            tip,
+            remote: config
+                .string_by("branch", subsection, "remote")
+                .map(|value| value.to_string()),
+            merge: config
+                .string_by("branch", subsection, "merge")
+                .map(|value| value.to_string()),
+            targets: branch.targets.clone(),
+            bundle: self.bundle.lock().unwrap().clone(),
+        }))
+    }
+
+    /// Recreates a branch from its journal entry, failing without changing anything if it cannot
+    /// be restored.
+    fn restore_entry(&self, entry: &JournalEntry) -> Result<(), Error> {
+        self.check_restorable(entry)?;
+
+        self.backend
+            .set_reference(&format!("refs/heads/{}", entry.name), &entry.tip)?;
+
+        if let (Some(remote), Some(merge)) = (&entry.remote, &entry.merge) {
+            self.backend.set_upstream(&entry.name, remote, merge)?;
         }
 
         Ok(())
     }
 
-    fn delete_all_branches(&self, branches: Vec<Branch>) -> Result<(), io::Error> {
-        println!();
-        for branch in &branches {
-            if self.delete_branch(&branch.name)? {
-                println!(
-                    "{}",
-                    self.localization.get_message_with_one_arg(
-                        "branch-deleted",
-                        String::from("branch"),
-                        branch.name.bold().to_string(),
-                    )
-                );
-            } else {
-                println!(
-                    "{}",
-                    self.localization.get_message_with_one_arg(
-                        "branch-cannot-be-deleted",
-                        String::from("branch"),
-                        branch.name.bold().to_string(),
-                    )
-                );
-            }
+    fn check_restorable(&self, entry: &JournalEntry) -> Result<(), Error> {
+        if self.is_local_branch(&entry.name)? {
+            return Err(Error::BranchExists {
+                branch: entry.name.clone(),
+            });
+        }
+
+        if self.backend.resolve_commit(&entry.tip)?.is_none() {
+            return Err(Error::CommitNotFound {
+                branch: entry.name.clone(),
+                tip: entry.tip.clone(),
+            });
         }
 
         Ok(())
     }
 
-    fn ask_delete_all_branches(&self, branches: Vec<Branch>) -> Result<(), io::Error> {
-        println!();
+    fn get_journal_path(&self) -> Result<PathBuf, Error> {
+        Ok(journal::journal_path(&self.get_git_directories()?.1))
+    }
+
+    /// Records the tip of a branch in the archive right before deleting it, unless archiving is
+    /// disabled. Returns the archive reference.
+    fn archive_branch(&self, branch: &Branch) -> Result<Option<String>, Error> {
+        let enabled = match self.options.archive {
+            Some(archive) => archive,
+            None => self.get_config_bool("broom.archive", true)?,
+        };
 
-        let yes = self
-            .localization
-            .get_message("choice-yes")
-            .chars()
-            .next()
-            .unwrap();
+        if !enabled {
+            return Ok(None);
+        }
+
+        let tip = match self.backend.resolve_commit(&branch.ref_name())? {
+            Some(tip) => tip,
+            None => return Ok(None),
+        };
+
+        let reference = archive_reference(&branch.ref_name(), &journal::run_id());
+        self.backend.set_reference(&reference, &tip)?;
+
+        Ok(Some(reference))
+    }
+
+    /// Returns the linked worktree a branch was found checked out in, if it still is. It may have
+    /// been removed by an earlier attempt to delete the branch.
+    fn get_checked_out_worktree(&self, branch: &Branch) -> Result<Option<PathBuf>, Error> {
+        let worktree = match &branch.worktree {
+            Some(worktree) => worktree,
+            None => return Ok(None),
+        };
 
-        for branch in &branches {
-            let message: String;
+        Ok(self
+            .backend
+            .worktrees()?
+            .into_iter()
+            .find(|(path, name)| path == worktree && name == &branch.name)
+            .map(|(path, _)| path))
+    }
+
+    /// Deletes a branch from its remote. The deletion is refused if the branch was updated on the
+    /// remote since it was last fetched.
+    fn delete_remote_branch(&self, remote: &str, branch: &Branch) -> Result<(), Error> {
+        let remote_branch = branch
+            .name
+            .strip_prefix(&format!("{}/", remote))
+            .unwrap_or(&branch.name);
+
+        self.backend.delete_remote_branch(remote, remote_branch)
+    }
 
-            if branch.protected {
-                message = self.localization.get_message_with_one_arg(
-                    "delete-protected-branch-yes-no",
-                    String::from("branch"),
-                    branch.name.bold().to_string(),
-                );
+    /// Checks again, right before deleting it, that a branch is still an ancestor of one of its
+    /// target branches.
+    fn is_merged_on_any(&self, branch: &str, targets: &[String]) -> Result<bool, Error> {
+        for target in targets {
+            if self
+                .backend
+                .is_ancestor(&format!("refs/heads/{}", branch), target)?
+            {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Tells whether a safe delete of a local branch goes through, the way `git branch -d` does:
+    /// the branch must be merged on its upstream, or on `HEAD` when it has none.
+    fn is_merged_on_upstream_or_head(&self, branch: &str) -> Result<bool, Error> {
+        let reference = self
+            .get_upstream(branch)?
+            .unwrap_or_else(|| String::from("HEAD"));
+
+        self.backend
+            .is_ancestor(&format!("refs/heads/{}", branch), &reference)
+    }
+
+    /// Resolves the branches to check if local branches are merged on, from the command line, then
+    /// from the `broom.targets` configuration, and finally falling back to the current branch.
+    ///
+    /// Each value is either the name of a branch or revision, which must exist, or a regular
+    /// expression starting with `^`, matched against the whole local branch names.
+    fn get_target_branches(&self, notices: &mut Vec<Notice>) -> Result<Vec<String>, Error> {
+        let patterns: Vec<String> = if !self.options.branches.is_empty() {
+            self.options.branches.clone()
+        } else if self.options.default_target {
+            vec![self.get_default_branch(notices)?]
+        } else {
+            let targets = self.get_config_list("broom.targets")?;
+
+            if targets.is_empty() && self.get_config_bool("broom.defaulttarget", false)? {
+                vec![self.get_default_branch(notices)?]
             } else {
-                message = self.localization.get_message_with_one_arg(
-                    "delete-branch-yes-no",
-                    String::from("branch"),
-                    branch.name.bold().to_string(),
-                );
+                targets
             }
+        };
 
-            let user_choice_result = self.read_user_input(message + " ", 'n');
-
-            if user_choice_result.is_ok() {
-                let user_choice = user_choice_result.unwrap();
-
-                if user_choice == yes {
-                    if self.delete_branch(&branch.name)? {
-                        println!(
-                            "{}",
-                            self.localization.get_message_with_one_arg(
-                                "branch-deleted",
-                                String::from("branch"),
-                                branch.name.bold().to_string(),
-                            )
-                        );
-                    } else {
-                        println!(
-                            "{}",
-                            self.localization.get_message_with_one_arg(
-                                "branch-cannot-be-deleted",
-                                String::from("branch"),
-                                branch.name.bold().to_string(),
-                            )
-                        );
-                    }
+        let patterns: Vec<String> = patterns
+            .iter()
+            .map(|pattern| pattern.trim().to_string())
+            .filter(|pattern| !pattern.is_empty())
+            .collect();
+
+        let mut targets: Vec<String> = Vec::new();
+
+        if patterns.is_empty() {
+            targets.push(self.get_current_branch()?);
+        } else {
+            let local_branches = self.get_local_branches()?;
+            let mut missing: Vec<String> = Vec::new();
+
+            for pattern in &patterns {
+                if pattern.starts_with('^') {
+                    let regex = Regex::new(&format!("^(?:{})$", pattern)).map_err(|source| {
+                        Error::InvalidTargetPattern {
+                            pattern: pattern.clone(),
+                            source,
+                        }
+                    })?;
+                    local_branches
+                        .iter()
+                        .filter(|branch| regex.is_match(branch))
+                        .for_each(|branch| targets.push(branch.clone()));
+                } else if self.is_valid_revision(pattern)? {
+                    targets.push(pattern.clone());
                 } else {
-                    println!(
-                        "{}",
-                        self.localization.get_message_with_one_arg(
-                            "branch-has-not-been-deleted",
-                            String::from("branch"),
-                            branch.name.bold().to_string(),
-                        )
-                    );
+                    missing.push(pattern.clone());
                 }
-            } else {
-                println!(
-                    "{}",
-                    self.localization.get_message_with_one_arg(
-                        "branch-has-not-been-deleted",
-                        String::from("branch"),
-                        branch.name.bold().to_string(),
-                    )
-                );
+            }
+
+            if !missing.is_empty() {
+                return Err(Error::NoSuchTarget { targets: missing });
+            }
+        }
+
+        let mut unique_targets: Vec<String> = Vec::new();
+        for target in targets {
+            if !target.is_empty() && !unique_targets.contains(&target) {
+                unique_targets.push(target);
+            }
+        }
+
+        if unique_targets.is_empty() {
+            return Err(Error::NoTargetBranch);
+        }
+
+        Ok(unique_targets)
+    }
+
+    /// Resolves the default branch of the repository from the `HEAD` of its remotes, `origin`
+    /// first, and falls back to the `init.defaultBranch` configuration.
+    ///
+    /// The remote-tracking branch is returned when the default branch has no local copy.
+    fn get_default_branch(&self, notices: &mut Vec<Notice>) -> Result<String, Error> {
+        let mut remotes = self.get_remotes()?;
+        if let Some(position) = remotes.iter().position(|remote| remote.eq("origin")) {
+            let origin = remotes.remove(position);
+            remotes.insert(0, origin);
+        }
+
+        for remote in &remotes {
+            let remote_head = format!("refs/remotes/{}/HEAD", remote);
+
+            if let Some(remote_branch) = self
+                .backend
+                .symbolic_ref_target(&remote_head)?
+                .map(|target| short_ref_name(&target))
+            {
+                let branch = remote_branch
+                    .strip_prefix(&format!("{}/", remote))
+                    .unwrap_or(&remote_branch)
+                    .to_string();
+
+                let branch = if self.is_local_branch(&branch)? {
+                    branch
+                } else {
+                    remote_branch
+                };
+
+                notices.push(Notice::DefaultBranch {
+                    branch: branch.clone(),
+                    source: remote_head,
+                });
+
+                return Ok(branch);
             }
         }
 
+        if let Some(branch) = self.get_config_value("init.defaultBranch")? {
+            if self.is_local_branch(&branch)? {
+                notices.push(Notice::DefaultBranch {
+                    branch: branch.clone(),
+                    source: String::from("init.defaultBranch"),
+                });
+
+                return Ok(branch);
+            }
+        }
+
+        Err(Error::NoDefaultBranch)
+    }
+
+    fn get_remotes(&self) -> Result<Vec<String>, Error> {
+        self.backend.remotes()
+    }
+
+    /// Fetches the relevant remotes with pruning, noting the remote-tracking branches which were
+    /// pruned. A failed fetch is only noted, as the local information is still usable.
+    fn fetch(&self, targets: &[String], notices: &mut Vec<Notice>) -> Result<(), Error> {
+        for remote in self.get_fetch_remotes(targets)? {
+            let before = self.get_remote_tracking_branches(&remote)?;
+
+            if !self.backend.fetch(&remote)? {
+                notices.push(Notice::FetchFailed { remote });
+                continue;
+            }
+
+            let after = self.get_remote_tracking_branches(&remote)?;
+            let pruned: Vec<String> = before.into_iter().filter(|r| !after.contains(r)).collect();
+
+            notices.push(Notice::Fetched { remote, pruned });
+        }
+
         Ok(())
     }
 
-    fn delete_branch(&self, branch: &String) -> Result<bool, io::Error> {
-        let output = Command::new("git")
-            .arg("branch")
-            .arg("-d")
-            .arg(branch)
-            .output()?;
+    /// Returns the remote whose branches are scanned, along with the remotes tracked by the local
+    /// branches or holding a target branch, or all the remotes if none is.
+    fn get_fetch_remotes(&self, targets: &[String]) -> Result<Vec<String>, Error> {
+        let all_remotes = self.get_remotes()?;
+
+        let mut remotes: Vec<String> = Vec::new();
+
+        self.options
+            .remote
+            .iter()
+            .cloned()
+            .chain(
+                self.backend
+                    .local_branches()?
+                    .into_iter()
+                    .filter_map(|branch| branch.upstream_remote),
+            )
+            .chain(targets.iter().filter_map(|target| {
+                all_remotes
+                    .iter()
+                    .find(|remote| target.starts_with(&format!("{}/", remote)))
+                    .cloned()
+            }))
+            .for_each(|remote| {
+                if all_remotes.contains(&remote) && !remotes.contains(&remote) {
+                    remotes.push(remote);
+                }
+            });
+
+        if remotes.is_empty() {
+            Ok(all_remotes)
+        } else {
+            Ok(remotes)
+        }
+    }
+
+    /// Returns the short names of the remote-tracking branches of a remote, such as `origin/main`.
+    fn get_remote_tracking_branches(&self, remote: &str) -> Result<Vec<String>, Error> {
+        let prefix = format!("refs/remotes/{}/", remote);
 
-        Ok(output.status.success())
+        Ok(self
+            .backend
+            .remote_tracking_branches()?
+            .iter()
+            .filter(|name| name.starts_with(&prefix))
+            .map(|name| short_ref_name(name))
+            .collect())
     }
 
-    fn get_working_branch(&self) -> Result<String, io::Error> {
-        let working_branch = match &self.branch {
-            None => self.get_current_branch(),
-            Some(branch) => Ok(branch.trim().to_string()),
-        }?;
+    /// Replaces each target branch by its upstream branch, keeping the local branch when it has no
+    /// upstream.
+    fn get_upstream_branches(
+        &self,
+        targets: Vec<String>,
+        notices: &mut Vec<Notice>,
+    ) -> Result<Vec<String>, Error> {
+        let mut upstream_targets: Vec<String> = Vec::new();
 
-        if working_branch.is_empty() {
-            return Err(io::Error::new(
-                ErrorKind::Other,
-                self.localization.get_message("no-valid-branch-found"),
-            ));
+        for target in targets {
+            match self.get_upstream(&target)? {
+                Some(upstream) => {
+                    if !upstream_targets.contains(&upstream) {
+                        upstream_targets.push(upstream);
+                    }
+                }
+                None => {
+                    notices.push(Notice::NoUpstream {
+                        branch: target.clone(),
+                    });
+
+                    if !upstream_targets.contains(&target) {
+                        upstream_targets.push(target);
+                    }
+                }
+            }
+        }
+
+        Ok(upstream_targets)
+    }
+
+    /// Warns about target branches which are behind their upstream, as branches merged upstream
+    /// since the last pull cannot be found on them.
+    fn warn_behind_upstream(
+        &self,
+        targets: &[String],
+        notices: &mut Vec<Notice>,
+    ) -> Result<(), Error> {
+        for target in targets {
+            if let Some(upstream) = self.get_upstream(target)? {
+                let behind = self.backend.count_commits(target, &upstream)?;
+
+                if behind > 0 {
+                    notices.push(Notice::BehindUpstream {
+                        branch: target.clone(),
+                        upstream,
+                        commits: behind,
+                    });
+                }
+            }
         }
 
-        Ok(working_branch)
+        Ok(())
+    }
+
+    /// Returns the short name of the upstream branch of a local branch, if any.
+    fn get_upstream(&self, branch: &str) -> Result<Option<String>, Error> {
+        Ok(self
+            .backend
+            .local_branches()?
+            .into_iter()
+            .find(|local_branch| local_branch.name.eq(branch))
+            .filter(|local_branch| !local_branch.upstream_gone)
+            .and_then(|local_branch| local_branch.upstream))
+    }
+
+    fn is_local_branch(&self, branch: &str) -> Result<bool, Error> {
+        self.is_valid_revision(&format!("refs/heads/{}", branch))
     }
 
-    fn get_current_branch(&self) -> Result<String, io::Error> {
-        let output = Command::new("git")
-            .arg("rev-parse")
-            .arg("--abbrev-ref")
-            .arg("HEAD")
-            .output()?;
+    fn is_valid_revision(&self, revision: &str) -> Result<bool, Error> {
+        Ok(self.backend.resolve_commit(revision)?.is_some())
+    }
 
-        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    /// Returns the short name of the current branch, or `HEAD` if it is detached.
+    fn get_current_branch(&self) -> Result<String, Error> {
+        Ok(self
+            .backend
+            .head()?
+            .map(|name| short_ref_name(&name))
+            .unwrap_or_else(|| String::from("HEAD")))
     }
 
-    fn is_protected_branch(&self, branch: &String, protected_branches: &Vec<Regex>) -> bool {
+    fn is_protected_branch(&self, branch: &str, protected_branches: &[Regex]) -> bool {
         for protected_branch in protected_branches.iter() {
-            if protected_branch.is_match(&branch) {
+            if protected_branch.is_match(branch) {
                 return true;
             }
         }
@@ -373,67 +1025,557 @@ impl GitBroom {
         false
     }
 
-    fn get_merged_branches(&self, branch: &String) -> Result<Vec<Branch>, io::Error> {
-        let protected_branches = self.get_protected_branches();
+    fn get_merged_branches(&self, targets: &[String]) -> Result<Vec<Branch>, Error> {
+        let protected_branches = self.get_protected_branches()?;
+        let excluded_branches = self.get_excluded_branches(targets)?;
+        let current_branch = self.get_current_branch()?;
+
+        let mut branches: Vec<Branch> = Vec::new();
+
+        for target in targets {
+            self.backend
+                .merged_branches(target)?
+                .into_iter()
+                .for_each(|line| {
+                    if !line.is_empty()
+                        && !line.eq(&current_branch)
+                        && !excluded_branches.contains(&line)
+                    {
+                        match branches.iter_mut().find(|branch| branch.name.eq(&line)) {
+                            Some(branch) => branch.targets.push(target.clone()),
+                            None => {
+                                let branch = Branch {
+                                    name: String::from(&line),
+                                    protected: self.is_protected_branch(&line, &protected_branches),
+                                    status: MergeStatus::Merged,
+                                    targets: vec![target.clone()],
+                                    remote: None,
+                                    worktree: None,
+                                    tip: None,
+                                };
+
+                                branches.push(branch);
+                            }
+                        }
+                    }
+                });
+        }
+
+        if self.options.squash_merged || self.options.rebase_merged {
+            let merged_count = branches.len();
+
+            for name in self.get_local_branches()? {
+                if excluded_branches.contains(&name)
+                    || name.eq(&current_branch)
+                    || branches[..merged_count]
+                        .iter()
+                        .any(|merged| merged.name.eq(&name))
+                {
+                    continue;
+                }
+
+                for target in targets {
+                    if let Some(status) = self.get_content_merge_status(target, &name)? {
+                        match branches.iter_mut().find(|branch| branch.name.eq(&name)) {
+                            Some(branch) => branch.targets.push(target.clone()),
+                            None => branches.push(Branch {
+                                name: name.clone(),
+                                protected: self.is_protected_branch(&name, &protected_branches),
+                                status,
+                                targets: vec![target.clone()],
+                                remote: None,
+                                worktree: None,
+                                tip: None,
+                            }),
+                        }
+                    }
+                }
+            }
+        }
 
-        let output = Command::new("git")
-            .arg("branch")
-            .arg("--merged")
-            .arg(branch)
-            .output()?;
+        self.set_worktrees(&mut branches)?;
+
+        Ok(branches)
+    }
+
+    /// Returns the branches of a remote merged on the target branches, from the remote-tracking
+    /// branches.
+    fn get_remote_merged_branches(
+        &self,
+        remote: &str,
+        targets: &[String],
+    ) -> Result<Vec<Branch>, Error> {
+        let protected_branches = self.get_protected_remote_branches()?;
+        let prefix = format!("{}/", remote);
+
+        // The default branch of the remote is never proposed, whatever the target branches.
+        let remote_head = self
+            .backend
+            .symbolic_ref_target(&format!("refs/remotes/{}/HEAD", remote))?
+            .map(|target| short_ref_name(&target));
+
+        let remote_branches: Vec<String> = self
+            .get_remote_tracking_branches(remote)?
+            .into_iter()
+            .filter(|name| !targets.contains(name) && remote_head.as_ref() != Some(name))
+            .collect();
 
         let mut branches: Vec<Branch> = Vec::new();
 
-        String::from_utf8_lossy(&output.stdout)
-            .lines()
-            .for_each(|line| {
-                let line = line.trim().to_string();
-                if !line.starts_with('*') && !line.eq(branch) {
-                    let branch = Branch {
-                        name: String::from(&line),
-                        protected: self.is_protected_branch(&line, &protected_branches),
-                    };
-
-                    branches.push(branch);
+        for name in &remote_branches {
+            let remote_branch = name.strip_prefix(&prefix).unwrap_or(name);
+            let mut merged_targets: Vec<String> = Vec::new();
+            let mut status: Option<MergeStatus> = None;
+
+            for target in targets {
+                if self
+                    .backend
+                    .is_ancestor(&format!("refs/remotes/{}", name), target)?
+                {
+                    merged_targets.push(target.clone());
+                    status = Some(MergeStatus::Merged);
+                }
+            }
+
+            if status.is_none() {
+                for target in targets {
+                    if let Some(content_status) = self.get_content_merge_status(target, name)? {
+                        merged_targets.push(target.clone());
+                        status.get_or_insert(content_status);
+                    }
+                }
+            }
+
+            if let Some(status) = status {
+                branches.push(Branch {
+                    name: name.clone(),
+                    protected: self.is_protected_branch(remote_branch, &protected_branches),
+                    status,
+                    targets: merged_targets,
+                    remote: Some(remote.to_string()),
+                    worktree: None,
+                    tip: None,
+                });
+            }
+        }
+
+        Ok(branches)
+    }
+
+    /// Returns the remote-tracking branches whose remote is no longer configured, or which no longer
+    /// exist on their remote. Remotes which cannot be reached are skipped with a notice.
+    fn get_stale_refs(&self, notices: &mut Vec<Notice>) -> Result<Vec<Branch>, Error> {
+        let remotes = self.get_remotes()?;
+
+        let remote_refs = self.backend.remote_tracking_branches()?;
+
+        let mut stale_refs: Vec<Branch> = Vec::new();
+
+        for remote_ref in remote_refs.iter().filter(|remote_ref| {
+            !remotes
+                .iter()
+                .any(|remote| remote_ref.starts_with(&format!("refs/remotes/{}/", remote)))
+        }) {
+            stale_refs.push(Branch {
+                name: remote_ref
+                    .strip_prefix("refs/remotes/")
+                    .unwrap_or(remote_ref)
+                    .to_string(),
+                protected: false,
+                status: MergeStatus::RemoteRemoved,
+                targets: Vec::new(),
+                remote: None,
+                worktree: None,
+                tip: None,
+            });
+        }
+
+        for remote in &remotes {
+            let remote_branches: Vec<String> = match self.backend.remote_heads(remote)? {
+                Some(heads) => heads
+                    .iter()
+                    .map(|name| format!("refs/remotes/{}/{}", remote, name))
+                    .collect(),
+                None => {
+                    notices.push(Notice::RemoteUnreachable {
+                        remote: remote.clone(),
+                    });
+                    continue;
                 }
+            };
+
+            let prefix = format!("refs/remotes/{}/", remote);
+
+            for remote_ref in remote_refs.iter().filter(|remote_ref| {
+                remote_ref.starts_with(&prefix) && !remote_branches.contains(remote_ref)
+            }) {
+                stale_refs.push(Branch {
+                    name: remote_ref
+                        .strip_prefix("refs/remotes/")
+                        .unwrap_or(remote_ref)
+                        .to_string(),
+                    protected: false,
+                    status: MergeStatus::StaleRemoteBranch,
+                    targets: Vec::new(),
+                    remote: None,
+                    worktree: None,
+                    tip: None,
+                });
+            }
+        }
+
+        Ok(stale_refs)
+    }
+
+    /// Maps each local target branch to its copy on the remote, when it exists.
+    fn get_remote_targets(&self, remote: &str, targets: Vec<String>) -> Result<Vec<String>, Error> {
+        if !self.get_remotes()?.iter().any(|name| name.eq(remote)) {
+            return Err(Error::NoSuchRemote {
+                remote: remote.to_string(),
             });
+        }
+
+        let mut remote_targets: Vec<String> = Vec::new();
+
+        for target in targets {
+            let remote_target = format!("{}/{}", remote, target);
+
+            let target = if self.is_local_branch(&target)?
+                && self.is_valid_revision(&format!("refs/remotes/{}", remote_target))?
+            {
+                remote_target
+            } else {
+                target
+            };
+
+            if !remote_targets.contains(&target) {
+                remote_targets.push(target);
+            }
+        }
+
+        Ok(remote_targets)
+    }
+
+    /// Returns the branches whose upstream branch no longer exists on the remote, and which were not
+    /// already found merged.
+    fn get_gone_branches(
+        &self,
+        targets: &[String],
+        merged_branches: &[Branch],
+    ) -> Result<Vec<Branch>, Error> {
+        let protected_branches = self.get_protected_branches()?;
+        let excluded_branches = self.get_excluded_branches(targets)?;
+        let current_branch = self.get_current_branch()?;
+
+        let mut branches: Vec<Branch> = Vec::new();
+
+        for local_branch in self.backend.local_branches()? {
+            let name = local_branch.name;
+
+            if local_branch.upstream_gone
+                && !name.eq(&current_branch)
+                && !excluded_branches.contains(&name)
+                && !merged_branches.iter().any(|merged| merged.name.eq(&name))
+            {
+                branches.push(Branch {
+                    protected: self.is_protected_branch(&name, &protected_branches),
+                    name,
+                    status: MergeStatus::UpstreamGone,
+                    targets: Vec::new(),
+                    remote: None,
+                    worktree: None,
+                    tip: None,
+                });
+            }
+        }
+
+        self.set_worktrees(&mut branches)?;
 
         Ok(branches)
     }
 
-    fn get_protected_branches(&self) -> Vec<Regex> {
-        match &mut env::current_dir() {
-            Ok(path) => {
-                path.push(".git");
-                match gix_config::File::from_git_dir(path.clone()) {
-                    Ok(file) => match file.string_by_key("broom.protectedbranches") {
-                        Some(branches) => branches
-                            .to_string()
-                            .split(",")
-                            .map(String::from)
-                            .filter_map(|re| Regex::new(&re).ok())
-                            .collect(),
-                        None => Vec::new(),
-                    },
-                    Err(_) => Vec::new(),
+    /// Sets the linked worktree of the branches checked out in one.
+    fn set_worktrees(&self, branches: &mut [Branch]) -> Result<(), Error> {
+        let worktrees = self.backend.worktrees()?;
+
+        for branch in branches.iter_mut() {
+            branch.worktree = worktrees
+                .iter()
+                .find(|(_, name)| name.eq(&branch.name))
+                .map(|(path, _)| path.clone());
+        }
+
+        Ok(())
+    }
+
+    /// Returns the target branches along with the local branches tracking them, which must never
+    /// be proposed for deletion.
+    fn get_excluded_branches(&self, targets: &[String]) -> Result<Vec<String>, Error> {
+        let mut excluded_branches: Vec<String> = targets.to_vec();
+
+        for local_branch in self.backend.local_branches()? {
+            if let Some(upstream) = &local_branch.upstream {
+                if targets.contains(upstream) {
+                    excluded_branches.push(local_branch.name);
                 }
             }
-            Err(_) => Vec::new(),
         }
+
+        Ok(excluded_branches)
     }
 
-    fn read_user_input(&self, message: String, default: char) -> Result<char, io::Error> {
-        print!("{}", message);
-        io::stdout().flush().unwrap();
+    /// Looks for the changes of a branch which is not an ancestor of the target branch, using the
+    /// enabled detection strategies.
+    fn get_content_merge_status(
+        &self,
+        target: &str,
+        branch: &str,
+    ) -> Result<Option<MergeStatus>, Error> {
+        if self.options.rebase_merged {
+            if let Some(commits) = self.backend.rebase_merged_commits(target, branch)? {
+                return Ok(Some(MergeStatus::RebaseMerged(commits)));
+            }
+        }
 
-        let mut choice = String::new();
+        if self.options.squash_merged && self.backend.is_squash_merged(target, branch)? {
+            return Ok(Some(MergeStatus::SquashMerged));
+        }
 
-        io::stdin().read_line(&mut choice)?;
+        Ok(None)
+    }
 
-        if !choice.is_empty() && choice.trim().len() == 1 {
-            Ok(choice.to_lowercase().chars().next().unwrap())
-        } else {
-            Ok(default)
+    fn get_local_branches(&self) -> Result<Vec<String>, Error> {
+        Ok(self
+            .backend
+            .local_branches()?
+            .into_iter()
+            .map(|branch| branch.name)
+            .collect())
+    }
+
+    fn get_protected_branches(&self) -> Result<Vec<Regex>, Error> {
+        self.get_protected_patterns(PROTECTED_BRANCHES_KEY)
+    }
+
+    fn get_protected_remote_branches(&self) -> Result<Vec<Regex>, Error> {
+        self.get_protected_patterns(PROTECTED_REMOTE_BRANCHES_KEY)
+    }
+
+    /// Reads the regular expressions protecting branches. An invalid expression is an error rather
+    /// than being ignored, so that protection never silently vanishes.
+    fn get_protected_patterns(&self, key: &str) -> Result<Vec<Regex>, Error> {
+        Ok(self
+            .get_protection_rules(key)?
+            .into_iter()
+            .map(|rule| rule.pattern)
+            .collect())
+    }
+
+    /// Reads the protection rules from every configuration file of the cascade: system, global,
+    /// XDG, local and worktree, along with their includes.
+    ///
+    /// Unlike other settings, where the most specific file wins, the rules of all the files are
+    /// combined, so that a local list never disables a global one.
+    fn get_protection_rules(&self, key: &str) -> Result<Vec<ProtectionRule>, Error> {
+        let (section_name, value_name) = key.split_once('.').unwrap_or((key, ""));
+        let file = self.get_config_file()?;
+
+        let mut rules: Vec<ProtectionRule> = Vec::new();
+
+        if let Some(sections) = file.sections_by_name(section_name) {
+            for section in sections.filter(|section| section.header().subsection_name().is_none()) {
+                let origin = match &section.meta().path {
+                    Some(path) => path.display().to_string(),
+                    None => format!("{:?}", section.meta().source),
+                };
+
+                for values in section.values(value_name) {
+                    for pattern in values.to_string().split(',') {
+                        let pattern = pattern.trim();
+
+                        if pattern.is_empty() {
+                            continue;
+                        }
+
+                        match Regex::new(pattern) {
+                            Ok(regex) => rules.push(ProtectionRule {
+                                key: key.to_string(),
+                                pattern: regex,
+                                origin: origin.clone(),
+                            }),
+                            Err(e) => {
+                                return Err(Error::InvalidProtectionRule {
+                                    key: key.to_string(),
+                                    pattern: pattern.to_string(),
+                                    origin,
+                                    source: e,
+                                })
+                            }
+                        }
+                    }
+                }
+            }
         }
+
+        Ok(rules)
+    }
+
+    /// Reads every value of a key from the Git configuration, each a comma-separated list.
+    fn get_config_list(&self, key: &str) -> Result<Vec<String>, Error> {
+        Ok(self
+            .get_config_file()?
+            .strings(key)
+            .unwrap_or_default()
+            .iter()
+            .flat_map(|values| {
+                values
+                    .to_string()
+                    .split(',')
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+            })
+            .collect())
+    }
+
+    fn get_config_bool(&self, key: &str, default: bool) -> Result<bool, Error> {
+        Ok(self
+            .get_config_file()?
+            .boolean(key)
+            .and_then(|value| value.ok())
+            .unwrap_or(default))
     }
+
+    fn get_config_value(&self, key: &str) -> Result<Option<String>, Error> {
+        Ok(self
+            .get_config_file()?
+            .string(key)
+            .map(|value| value.to_string()))
+    }
+
+    /// Loads the Git configuration of the repository the way Git does: system, global and XDG files,
+    /// then the local file of the common Git directory and the worktree file of the current
+    /// worktree, resolving `include` and `includeIf` sections, and finally the `GIT_CONFIG_*`
+    /// environment overrides.
+    ///
+    /// Failing to do so is an error, as the protected branches would otherwise be lost.
+    fn get_config_file(&self) -> Result<gix_config::File<'static>, Error> {
+        self.load_config_file().map_err(Error::Config)
+    }
+
+    fn load_config_file(
+        &self,
+    ) -> Result<gix_config::File<'static>, Box<dyn error::Error + Send + Sync>> {
+        let (git_dir, common_dir) = self.get_git_directories()?;
+
+        let mut local = gix_config::File::from_path_no_includes(
+            common_dir.join("config"),
+            gix_config::Source::Local,
+        )?;
+
+        let worktree_path = git_dir.join("config.worktree");
+        let worktree = match local.boolean("extensions.worktreeConfig") {
+            Some(Ok(true)) if worktree_path.is_file() => {
+                Some(gix_config::File::from_path_no_includes(
+                    worktree_path,
+                    gix_config::Source::Worktree,
+                )?)
+            }
+            _ => None,
+        };
+
+        let branch_name = self
+            .backend
+            .head()?
+            .and_then(|branch| gix_ref::FullName::try_from(branch.as_str()).ok());
+
+        let home = env::var_os("HOME").map(PathBuf::from);
+        let options = gix_config::file::init::Options {
+            includes: gix_config::file::includes::Options::follow(
+                gix_config::path::interpolate::Context {
+                    home_dir: home.as_deref(),
+                    ..Default::default()
+                },
+                gix_config::file::includes::conditional::Context {
+                    git_dir: Some(git_dir.as_ref()),
+                    branch_name: branch_name.as_ref().map(|name| name.as_ref()),
+                },
+            ),
+            ..Default::default()
+        };
+
+        let mut env_var = |name: &str| env::var_os(name);
+        let global_files = [
+            gix_config::source::Kind::System,
+            gix_config::source::Kind::Global,
+        ]
+        .iter()
+        .flat_map(|kind| kind.sources())
+        .filter_map(|source| {
+            source
+                .storage_location(&mut env_var)
+                .filter(|path| path.is_file())
+                .map(|path| gix_config::file::Metadata::from(*source).at(path.into_owned()))
+        });
+
+        let mut config =
+            gix_config::File::from_paths_metadata(global_files, options)?.unwrap_or_default();
+
+        local.resolve_includes(options)?;
+        config.append(local);
+
+        if let Some(mut worktree) = worktree {
+            worktree.resolve_includes(options)?;
+            config.append(worktree);
+        }
+
+        config.append(gix_config::File::from_environment_overrides()?);
+
+        Ok(config)
+    }
+
+    /// Discovers the Git directory of the current worktree and the common Git directory of the
+    /// repository the way Git does, from a subdirectory, a linked worktree or a bare repository,
+    /// honouring `GIT_DIR` and `GIT_WORK_TREE`.
+    fn get_git_directories(&self) -> Result<(PathBuf, PathBuf), Error> {
+        self.backend.git_directories()
+    }
+}
+
+/// Turns the result of a deletion into a status, a refusal of Git being a failed deletion rather
+/// than an error.
+fn deletion_status(result: Result<(), Error>) -> Result<DeletionStatus, Error> {
+    match result {
+        Ok(()) => Ok(DeletionStatus::Deleted),
+        Err(Error::Deletion(e)) => Ok(DeletionStatus::Failed(e)),
+        Err(e) => Err(e),
+    }
+}
+
+/// The refusal of Git to delete a branch checked out in a worktree.
+fn checked_out_error(branch: &Branch, worktree: &Path) -> DeletionError {
+    DeletionError::new(
+        DeletionErrorKind::CheckedOut,
+        format!(
+            "error: cannot delete branch '{}' used by worktree at '{}'",
+            branch.name,
+            worktree.display()
+        ),
+    )
+}
+
+/// The refusal of Git to safely delete a branch which is not merged.
+fn not_fully_merged_error(branch: &Branch) -> DeletionError {
+    DeletionError::new(
+        DeletionErrorKind::NotFullyMerged,
+        format!("error: the branch '{}' is not fully merged", branch.name),
+    )
+}
+
+/// Shortens the full name of a branch, such as `refs/heads/main` or `refs/remotes/origin/main`,
+/// the way Git does.
+fn short_ref_name(name: &str) -> String {
+    name.strip_prefix("refs/heads/")
+        .or_else(|| name.strip_prefix("refs/remotes/"))
+        .unwrap_or(name)
+        .to_string()
 }
@@ -16,7 +16,8 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <http://www.gnu.org/licenses/>.
 */
 
-use fluent::{FluentArgs, FluentBundle, FluentResource};
+use fluent::concurrent::FluentBundle;
+use fluent::{FluentArgs, FluentResource};
 use fluent_langneg::{negotiate_languages, NegotiationStrategy};
 use rust_embed::RustEmbed;
 use sys_locale::get_locale;
@@ -30,6 +31,12 @@ pub struct Localization {
     bundle: FluentBundle<FluentResource>,
 }
 
+impl Default for Localization {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Localization {
     pub fn new() -> Self {
         Self {
@@ -48,6 +55,18 @@ impl Localization {
         self.get_message_with_args(id, args)
     }
 
+    pub fn get_message_with_two_args(
+        &self,
+        id: &str,
+        first_arg: (String, String),
+        second_arg: (String, String),
+    ) -> String {
+        let mut args = FluentArgs::new();
+        args.set(first_arg.0, first_arg.1);
+        args.set(second_arg.0, second_arg.1);
+        self.get_message_with_args(id, args)
+    }
+
     pub fn get_message_with_count(&self, id: &str, count: usize) -> String {
         let mut args = FluentArgs::new();
         args.set("count", count);
@@ -67,6 +86,20 @@ impl Localization {
         self.get_message_with_args(id, args)
     }
 
+    pub fn get_message_with_count_and_args(
+        &self,
+        id: &str,
+        count: usize,
+        named_args: Vec<(String, String)>,
+    ) -> String {
+        let mut args = FluentArgs::new();
+        args.set("count", count);
+        for (arg_name, arg_value) in named_args {
+            args.set(arg_name, arg_value);
+        }
+        self.get_message_with_args(id, args)
+    }
+
     pub fn get_message(&self, id: &str) -> String {
         if let Some(message) = self.bundle.get_message(id) {
             if let Some(pattern) = message.value() {
@@ -116,15 +149,13 @@ impl Localization {
             NegotiationStrategy::Filtering,
         );
 
-        let selected_locale = resolved_locales
-            .get(0)
-            .cloned()
-            .unwrap_or_else(|| &default_locale);
+        let selected_locale = resolved_locales.first().cloned().unwrap_or(&default_locale);
 
-        let data = LocalizationAsset::get(&format!("{}.ftl", selected_locale.to_string())).unwrap();
+        let data = LocalizationAsset::get(&format!("{}.ftl", selected_locale)).unwrap();
         let str_data = String::from_utf8_lossy(data.data.as_ref());
 
-        let mut bundle = FluentBundle::new(resolved_locales.into_iter().cloned().collect());
+        let mut bundle =
+            FluentBundle::new_concurrent(resolved_locales.into_iter().cloned().collect());
         bundle.set_use_isolating(false);
 
         if let Ok(resource) = FluentResource::try_new(str_data.parse().unwrap()) {
@@ -0,0 +1,135 @@
+/*
+Git Broom
+Copyright (C) 2024  All contributors.
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! The journal of deleted branches, from which they can be restored along with their upstream
+//! configuration. It is kept in the common Git directory as JSON lines, one per deletion.
+
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+/// A local branch deleted by Git Broom.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// Identifies the run which deleted the branch, shared by every deletion of a [`GitBroom`].
+    ///
+    /// [`GitBroom`]: crate::GitBroom
+    pub run: String,
+    /// When the branch was deleted, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// The short name of the branch, such as `feature`.
+    pub name: String,
+    /// The id of the commit the branch pointed to.
+    pub tip: String,
+    /// The `branch.<name>.remote` configuration of the branch.
+    pub remote: Option<String>,
+    /// The `branch.<name>.merge` configuration of the branch, such as `refs/heads/feature`.
+    pub merge: Option<String>,
+    /// The target branches the branch was found merged on.
+    pub targets: Vec<String>,
+    /// The path of the bundle the branch was saved to before being deleted, if any, lossily
+    /// converted when it is not valid UTF-8.
+    #[serde(default)]
+    pub bundle: Option<String>,
+}
+
+/// Returns the path of the journal, in the common Git directory.
+pub(crate) fn journal_path(common_dir: &Path) -> PathBuf {
+    common_dir.join("broom").join("journal")
+}
+
+/// Returns a new identifier of a run, unique to the process and the instant.
+pub(crate) fn run_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or(0);
+
+    format!("{}-{}", nanos, process::id())
+}
+
+/// Returns the seconds since the Unix epoch.
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// Reads the entries of a journal, oldest first. A missing journal is empty, and the lines which
+/// cannot be read, such as one cut short by a crash, are left out.
+pub(crate) fn read(path: &Path) -> std::io::Result<Vec<JournalEntry>> {
+    let file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut entries: Vec<JournalEntry> = Vec::new();
+
+    for line in BufReader::new(file).lines() {
+        if let Ok(entry) = serde_json::from_str(&line?) {
+            entries.push(entry);
+        }
+    }
+
+    Ok(entries)
+}
+
+pub(crate) fn append(path: &Path, entry: &JournalEntry) -> std::io::Result<()> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())
+}
+
+/// Removes an entry from a journal, such as the one of a branch which could not be deleted.
+pub(crate) fn remove(path: &Path, entry: &JournalEntry) -> std::io::Result<()> {
+    let entries: Vec<JournalEntry> = read(path)?
+        .into_iter()
+        .filter(|kept| kept != entry)
+        .collect();
+
+    write(path, &entries)
+}
+
+/// Replaces the entries of a journal, writing them to a temporary file first.
+pub(crate) fn write(path: &Path, entries: &[JournalEntry]) -> std::io::Result<()> {
+    let temporary_path = path.with_extension("tmp");
+    let mut file = fs::File::create(&temporary_path)?;
+
+    for entry in entries {
+        serde_json::to_writer(&mut file, entry)?;
+        file.write_all(b"\n")?;
+    }
+
+    file.sync_all()?;
+    fs::rename(&temporary_path, path)
+}
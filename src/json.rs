@@ -0,0 +1,238 @@
+/*
+Git Broom
+Copyright (C) 2024  All contributors.
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! The JSON output, built from the library types but kept apart from them so that its format stays
+//! stable.
+
+use std::borrow::Cow;
+use std::path::Path;
+
+use git_broom::{
+    Branch, Bundle, DeletionErrorKind, DeletionOutcome, DeletionStatus, MergeStatus, Notice, Report,
+};
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct JsonReport<'a> {
+    targets: &'a [String],
+    remote: Option<&'a str>,
+    notices: Vec<JsonNotice<'a>>,
+    branches: Vec<JsonBranch<'a>>,
+    /// Empty unless branches were deleted.
+    outcomes: Vec<JsonOutcome<'a>>,
+    /// The bundle the branches were saved to before being deleted, if any.
+    bundle: Option<JsonBundle<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonBundle<'a> {
+    /// Paths are written lossily, serde refusing those which are not valid UTF-8.
+    path: Cow<'a, str>,
+    /// The full names of the references saved.
+    references: &'a [String],
+}
+
+impl<'a> JsonReport<'a> {
+    /// `selected` tells whether a branch is proposed for deletion.
+    pub fn new(
+        report: &'a Report,
+        selected: impl Fn(&Branch) -> bool,
+        outcomes: &'a [DeletionOutcome],
+        bundle: Option<&'a Bundle>,
+    ) -> Self {
+        let categories = [
+            ("merged", Some(&report.merged)),
+            ("gone", report.gone.as_ref()),
+            ("stale", report.stale.as_ref()),
+        ];
+
+        let branches = categories
+            .into_iter()
+            .filter_map(|(category, branches)| branches.map(|branches| (category, branches)))
+            .flat_map(|(category, branches)| branches.iter().map(move |branch| (category, branch)))
+            .map(|(category, branch)| JsonBranch::new(branch, category, selected(branch)))
+            .collect();
+
+        Self {
+            targets: &report.targets,
+            remote: report.remote.as_deref(),
+            notices: report.notices.iter().map(JsonNotice::from).collect(),
+            branches,
+            outcomes: outcomes.iter().map(JsonOutcome::from).collect(),
+            bundle: bundle.map(|bundle| JsonBundle {
+                path: bundle.path.to_string_lossy(),
+                references: &bundle.references,
+            }),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("the report can always be serialized")
+    }
+}
+
+#[derive(Serialize)]
+struct JsonBranch<'a> {
+    name: &'a str,
+    /// `merged`, `gone` or `stale`.
+    category: &'static str,
+    reason: &'static str,
+    /// The number of commits found on the target branches, for a rebase-merged branch.
+    matched_commits: Option<usize>,
+    protected: bool,
+    selected: bool,
+    tip: Option<&'a str>,
+    targets: &'a [String],
+    remote: Option<&'a str>,
+    worktree: Option<Cow<'a, str>>,
+}
+
+impl<'a> JsonBranch<'a> {
+    fn new(branch: &'a Branch, category: &'static str, selected: bool) -> Self {
+        let (reason, matched_commits) = match branch.status {
+            MergeStatus::Merged => ("merged", None),
+            MergeStatus::SquashMerged => ("squash_merged", None),
+            MergeStatus::RebaseMerged(commits) => ("rebase_merged", Some(commits)),
+            MergeStatus::UpstreamGone => ("upstream_gone", None),
+            MergeStatus::RemoteRemoved => ("remote_removed", None),
+            MergeStatus::StaleRemoteBranch => ("stale_remote_branch", None),
+        };
+
+        Self {
+            name: &branch.name,
+            category,
+            reason,
+            matched_commits,
+            protected: branch.protected,
+            selected,
+            tip: branch.tip.as_deref(),
+            targets: &branch.targets,
+            remote: branch.remote.as_deref(),
+            worktree: branch.worktree.as_deref().map(Path::to_string_lossy),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum JsonNotice<'a> {
+    DefaultBranch {
+        branch: &'a str,
+        source: &'a str,
+    },
+    NoUpstream {
+        branch: &'a str,
+    },
+    BehindUpstream {
+        branch: &'a str,
+        upstream: &'a str,
+        commits: usize,
+    },
+    Fetched {
+        remote: &'a str,
+        pruned: &'a [String],
+    },
+    FetchFailed {
+        remote: &'a str,
+    },
+    RemoteUnreachable {
+        remote: &'a str,
+    },
+}
+
+impl<'a> From<&'a Notice> for JsonNotice<'a> {
+    fn from(notice: &'a Notice) -> Self {
+        match notice {
+            Notice::DefaultBranch { branch, source } => Self::DefaultBranch { branch, source },
+            Notice::NoUpstream { branch } => Self::NoUpstream { branch },
+            Notice::BehindUpstream {
+                branch,
+                upstream,
+                commits,
+            } => Self::BehindUpstream {
+                branch,
+                upstream,
+                commits: *commits,
+            },
+            Notice::Fetched { remote, pruned } => Self::Fetched { remote, pruned },
+            Notice::FetchFailed { remote } => Self::FetchFailed { remote },
+            Notice::RemoteUnreachable { remote } => Self::RemoteUnreachable { remote },
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonOutcome<'a> {
+    name: &'a str,
+    remote: Option<&'a str>,
+    /// `deleted`, `failed`, `protected`, `worktree_not_clean` or `error`.
+    status: &'static str,
+    /// Why the deletion failed.
+    error: Option<JsonDeletionError<'a>>,
+    removed_worktree: Option<Cow<'a, str>>,
+    /// The archive reference the tip of the deleted branch was recorded in.
+    archived: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct JsonDeletionError<'a> {
+    /// `not_fully_merged`, `checked_out`, `ref_locked`, `not_found`, `remote_rejected` or
+    /// `other`.
+    kind: &'static str,
+    /// The diagnostic of Git, or the error which kept the branch.
+    message: &'a str,
+}
+
+impl<'a> From<&'a DeletionOutcome> for JsonOutcome<'a> {
+    fn from(outcome: &'a DeletionOutcome) -> Self {
+        Self {
+            name: &outcome.branch.name,
+            remote: outcome.branch.remote.as_deref(),
+            status: match outcome.status {
+                DeletionStatus::Deleted => "deleted",
+                DeletionStatus::Failed(_) => "failed",
+                DeletionStatus::Protected => "protected",
+                DeletionStatus::WorktreeNotClean => "worktree_not_clean",
+                DeletionStatus::Error(_) => "error",
+            },
+            error: match &outcome.status {
+                DeletionStatus::Failed(e) => Some(JsonDeletionError {
+                    kind: match e.kind {
+                        DeletionErrorKind::NotFullyMerged => "not_fully_merged",
+                        DeletionErrorKind::CheckedOut => "checked_out",
+                        DeletionErrorKind::RefLocked => "ref_locked",
+                        DeletionErrorKind::NotFound => "not_found",
+                        DeletionErrorKind::RemoteRejected => "remote_rejected",
+                        DeletionErrorKind::Other => "other",
+                    },
+                    message: &e.message,
+                }),
+                DeletionStatus::Error(message) => Some(JsonDeletionError {
+                    kind: "other",
+                    message,
+                }),
+                _ => None,
+            },
+            removed_worktree: outcome
+                .removed_worktree
+                .as_deref()
+                .map(Path::to_string_lossy),
+            archived: outcome.archived.as_deref(),
+        }
+    }
+}
@@ -0,0 +1,52 @@
+/*
+Git Broom
+Copyright (C) 2024  All contributors.
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Finds the merged branches of a Git repository, and deletes them.
+//!
+//! ```no_run
+//! let broom = git_broom::GitBroom::builder().branch("main").gone(true).build();
+//!
+//! let report = broom.scan()?;
+//! let deletable: Vec<git_broom::Branch> = report
+//!     .branches()
+//!     .filter(|branch| !branch.protected)
+//!     .cloned()
+//!     .collect();
+//!
+//! for outcome in broom.apply(&deletable)? {
+//!     println!("{}: {:?}", outcome.branch.name, outcome.status);
+//! }
+//! # Ok::<(), git_broom::Error>(())
+//! ```
+
+mod archive;
+pub mod backend;
+mod branch;
+mod error;
+mod git;
+pub mod i18n;
+mod journal;
+mod report;
+
+pub use archive::ArchivedBranch;
+pub use backend::Backend;
+pub use branch::{Branch, Commit, MergeStatus, ProtectionRule};
+pub use error::{DeletionError, DeletionErrorKind, Error};
+pub use git::{GitBroom, GitBroomBuilder};
+pub use journal::JournalEntry;
+pub use report::{Bundle, DeletionOutcome, DeletionStatus, Notice, Report};
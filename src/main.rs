@@ -16,44 +16,240 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <http://www.gnu.org/licenses/>.
 */
 
-use std::error;
+use std::path::PathBuf;
+use std::process::ExitCode;
 
-use clap::Parser;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
 use colored::Colorize;
+use regex::Regex;
 
-mod git;
-mod i18n;
+use git_broom::backend::GixBackend;
+use git_broom::GitBroom;
+
+use cli::{CliOptions, Format, Summary};
+
+mod cli;
+mod json;
+
+/// Something failed: the scan, or the deletion of a branch.
+const EXIT_ERROR: u8 = 1;
+/// With `--check`, branches can be deleted.
+const EXIT_BRANCHES_FOUND: u8 = 3;
+/// With `--check`, no branch can be deleted, but protected branches were found.
+const EXIT_PROTECTED_BRANCHES_FOUND: u8 = 4;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
     /// Path of Git repository. Current path if not specified.
     repository: Option<String>,
-    /// Branch to check if local branches are merged on.
+    /// Branch to check if local branches are merged on. Can be repeated. A value starting with ^ is a regular expression matched against whole local branch names.
     #[arg(short, long)]
-    branch: Option<String>,
+    branch: Vec<String>,
+    /// Use the default branch of the repository, resolved from its remotes, as target branch.
+    #[arg(short = 't', long)]
+    default_target: bool,
     /// Print merged branches, do not propose to delete them.
     #[arg(short, long)]
     dry_run: bool,
+    /// Print merged branches like --dry-run, and exit with code 3 if some can be deleted, 4 if only protected ones were found, 0 otherwise.
+    #[arg(short, long, conflicts_with = "yes")]
+    check: bool,
+    /// With --check, the number of branches which can be deleted tolerated before exiting with code 3.
+    #[arg(long, value_name = "N", default_value_t = 0, requires = "check")]
+    max_branches: usize,
+    /// Delete all the branches proposed for deletion without asking, removing the clean worktrees they are checked out in. Use with care.
+    #[arg(short, long, conflicts_with = "dry_run")]
+    yes: bool,
+    /// When a branch is refused because it is not fully merged, offer to force delete it, listing the commits which would be lost and asking to type its name.
+    #[arg(long, conflicts_with_all = ["yes", "dry_run", "check", "porcelain"])]
+    force_unmerged: bool,
+    /// Save the branches proposed for deletion, with their history, to a bundle file before deleting any. Nothing is deleted if the bundle cannot be written and verified. Requires --yes with the JSON format.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["dry_run", "check", "porcelain"])]
+    bundle: Option<PathBuf>,
+    /// Only propose to delete the branches matching this regular expression. Can be repeated.
+    #[arg(long, value_name = "REGEX")]
+    select: Vec<Regex>,
+    /// How to print the branches. The JSON format never asks anything: branches are only deleted with --yes.
+    #[arg(long, value_enum, default_value_t = Format::Human)]
+    format: Format,
+    /// Only print the branches, one per line, with tab-separated fields which are neither localized nor coloured: name, category, reason, protected or unprotected, selected or unselected, tip, target branches and worktree.
+    #[arg(long, conflicts_with_all = ["format", "yes"])]
+    porcelain: bool,
+    /// With --porcelain, terminate the lines with NUL instead of a newline.
+    #[arg(short = 'z', requires = "porcelain")]
+    null: bool,
     /// Propose to delete protected branches, only printing warnings. Use with care.
     #[arg(short, long)]
     include_protected_branches: bool,
+    /// Also detect branches merged with "squash and merge", whose changes already exist as a single commit on the target branch.
+    #[arg(short, long)]
+    squash_merged: bool,
+    /// Also detect branches merged with "rebase and merge" or cherry-picked, whose every commit has an equivalent commit on the target branch.
+    #[arg(short, long)]
+    rebase_merged: bool,
+    /// Check if local branches are merged on the upstream of the target branches instead of their local copy.
+    #[arg(short, long)]
+    upstream: bool,
+    /// Also propose branches whose upstream branch was deleted from the remote, even if they are not found merged.
+    #[arg(short, long)]
+    gone: bool,
+    /// Fetch and prune the remotes before looking for merged branches.
+    #[arg(short, long)]
+    fetch: bool,
+    /// Clean up the branches of this remote merged on the target branches, instead of the local branches. Use with care, this affects everyone using the remote.
+    #[arg(long)]
+    remote: Option<String>,
+    /// Also propose remote-tracking branches of removed remotes, or which no longer exist on their remote.
+    #[arg(long)]
+    stale: bool,
+    /// Print the protected branch rules and the configuration file each one comes from.
+    #[arg(long)]
+    show_protection: bool,
+    /// Do not record the tip of the deleted branches under refs/broom/archive/<date>/<name>. Archiving can also be disabled with the broom.archive configuration.
+    #[arg(long)]
+    no_archive: bool,
+    /// How to work with the repository: run the installed Git command, or work in-process with gitoxide, which needs no Git but supports neither fetching, remote branches, squash-merged and rebase-merged branches nor worktree removal.
+    #[arg(long, value_enum, default_value_t = BackendKind::Git)]
+    backend: BackendKind,
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-fn main() -> Result<(), Box<dyn error::Error>> {
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Work with the archive of deleted branches.
+    #[command(subcommand)]
+    Archive(ArchiveCommand),
+    /// Recreate the last deleted branch with this name, along with its upstream configuration, from the journal of deletions.
+    Restore { branch: String },
+    /// Recreate every branch deleted by the last run, along with their upstream configuration.
+    Undo,
+}
+
+#[derive(Subcommand, Debug)]
+enum ArchiveCommand {
+    /// List the archived branches, oldest first.
+    List,
+    /// Delete the archived branches older than a retention period.
+    Purge {
+        /// The number of days archived branches are kept for.
+        #[arg(long, value_name = "DAYS", default_value_t = 30)]
+        older_than: u64,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum BackendKind {
+    Git,
+    Gitoxide,
+}
+
+fn main() -> ExitCode {
     let args = Args::parse();
 
-    match git::GitBroom::new(
-        args.repository,
-        args.branch,
-        args.dry_run,
-        args.include_protected_branches,
-    )
-    .broom()
-    {
-        Err(e) => println!("{}", e.to_string().red()),
-        Ok(_) => (),
+    // The JSON format only deletes branches with --yes, so a bundle would never be written.
+    if args.bundle.is_some() && args.format == Format::Json && !args.yes {
+        Args::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "--bundle with --format json requires --yes, as nothing is deleted without it",
+            )
+            .exit();
+    }
+
+    let mut builder = GitBroom::builder()
+        .branches(args.branch)
+        .default_target(args.default_target)
+        .include_protected_branches(args.include_protected_branches)
+        .squash_merged(args.squash_merged)
+        .rebase_merged(args.rebase_merged)
+        .upstream(args.upstream)
+        .gone(args.gone)
+        .fetch(args.fetch)
+        .stale(args.stale);
+
+    if args.no_archive {
+        builder = builder.archive(false);
     }
 
-    Ok(())
+    if args.backend == BackendKind::Gitoxide {
+        builder = builder.backend(GixBackend::new(args.repository.as_deref().unwrap_or(".")));
+    }
+
+    if let Some(repository) = args.repository {
+        builder = builder.repository(repository);
+    }
+
+    if let Some(remote) = args.remote {
+        builder = builder.remote(remote);
+    }
+
+    let format = if args.porcelain {
+        Format::Porcelain {
+            null_terminated: args.null,
+        }
+    } else {
+        args.format
+    };
+
+    let cli = cli::Cli::new(
+        builder.build(),
+        CliOptions {
+            dry_run: args.dry_run || args.check,
+            include_protected_branches: args.include_protected_branches,
+            yes: args.yes,
+            force_unmerged: args.force_unmerged,
+            bundle: args.bundle,
+            select: args.select,
+            format,
+        },
+    );
+
+    let result = if let Some(command) = args.command {
+        match command {
+            Command::Archive(ArchiveCommand::List) => cli.list_archive(),
+            Command::Archive(ArchiveCommand::Purge { older_than }) => cli.purge_archive(older_than),
+            Command::Restore { branch } => cli.restore(&branch),
+            Command::Undo => cli.undo(),
+        }
+        .map(|_| ExitCode::SUCCESS)
+    } else if args.show_protection {
+        cli.show_protection().map(|_| ExitCode::SUCCESS)
+    } else {
+        cli.broom()
+            .map(|summary| exit_code(&summary, args.check.then_some(args.max_branches)))
+    };
+
+    match result {
+        Ok(exit_code) => exit_code,
+        Err(e) => {
+            if format != Format::Human {
+                eprintln!("{}", e);
+            } else {
+                println!("{}", e.to_string().red());
+            }
+
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+/// Chooses the exit code of a run, checking the number of branches which can be deleted against
+/// `max_branches` in check mode.
+fn exit_code(summary: &Summary, max_branches: Option<usize>) -> ExitCode {
+    if summary.failed > 0 {
+        return ExitCode::from(EXIT_ERROR);
+    }
+
+    match max_branches {
+        Some(max_branches) if summary.proposed > max_branches => {
+            ExitCode::from(EXIT_BRANCHES_FOUND)
+        }
+        Some(_) if summary.proposed == 0 && summary.protected > 0 => {
+            ExitCode::from(EXIT_PROTECTED_BRANCHES_FOUND)
+        }
+        _ => ExitCode::SUCCESS,
+    }
 }
@@ -0,0 +1,116 @@
+/*
+Git Broom
+Copyright (C) 2024  All contributors.
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+use std::path::PathBuf;
+
+use crate::branch::Branch;
+use crate::error::DeletionError;
+
+/// The result of a scan: the branches which can be deleted, by category.
+#[derive(Clone, Debug, Default)]
+pub struct Report {
+    /// The target branches the branches were checked against.
+    pub targets: Vec<String>,
+    /// The remote whose branches were scanned, instead of the local branches.
+    pub remote: Option<String>,
+    /// The branches merged on the target branches.
+    pub merged: Vec<Branch>,
+    /// The branches whose upstream is gone, if they were looked for.
+    pub gone: Option<Vec<Branch>>,
+    /// The stale remote-tracking branches, if they were looked for.
+    pub stale: Option<Vec<Branch>>,
+    /// What happened along the way, in order.
+    pub notices: Vec<Notice>,
+}
+
+impl Report {
+    /// Returns the branches of every category.
+    pub fn branches(&self) -> impl Iterator<Item = &Branch> {
+        self.merged
+            .iter()
+            .chain(self.gone.iter().flatten())
+            .chain(self.stale.iter().flatten())
+    }
+}
+
+/// Something worth telling about a scan, which did not prevent it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Notice {
+    /// The default branch of the repository is used as target branch. `source` tells where it was
+    /// resolved from.
+    DefaultBranch {
+        branch: String,
+        source: String,
+    },
+    /// A target branch has no upstream, its local copy is used.
+    NoUpstream {
+        branch: String,
+    },
+    /// A target branch is behind its upstream by a number of commits.
+    BehindUpstream {
+        branch: String,
+        upstream: String,
+        commits: usize,
+    },
+    /// A remote was fetched, pruning these remote-tracking branches.
+    Fetched {
+        remote: String,
+        pruned: Vec<String>,
+    },
+    FetchFailed {
+        remote: String,
+    },
+    /// A remote could not be reached to look for stale remote-tracking branches.
+    RemoteUnreachable {
+        remote: String,
+    },
+}
+
+/// A bundle the branches about to be deleted were saved to, by [`crate::GitBroom::bundle`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bundle {
+    /// The absolute path of the bundle file.
+    pub path: PathBuf,
+    /// The full names of the references saved, such as `refs/heads/feature`.
+    pub references: Vec<String>,
+}
+
+/// What happened to a branch given to [`crate::GitBroom::apply`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeletionOutcome {
+    pub branch: Branch,
+    pub status: DeletionStatus,
+    /// The linked worktree removed so that the branch could be deleted.
+    pub removed_worktree: Option<PathBuf>,
+    /// The archive reference the tip of the deleted branch was recorded in.
+    pub archived: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeletionStatus {
+    Deleted,
+    /// Git refused to delete the branch, or to remove the worktree it is checked out in.
+    Failed(DeletionError),
+    /// The branch is protected, and protected branches are not included.
+    Protected,
+    /// The branch is checked out in a worktree with local changes, which was kept.
+    WorktreeNotClean,
+    /// Something other than a refusal of Git kept the branch, such as its tip failing to be
+    /// archived. Holds the error.
+    Error(String),
+}
@@ -0,0 +1,474 @@
+/*
+Git Broom
+Copyright (C) 2024  All contributors.
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+mod common;
+
+use common::{BackendKind, TestRepo, BACKENDS};
+use git_broom::{Branch, DeletionErrorKind, DeletionStatus, MergeStatus, Notice};
+
+fn merged(name: &str) -> Branch {
+    Branch {
+        name: name.to_string(),
+        protected: false,
+        status: MergeStatus::Merged,
+        targets: vec![String::from("main")],
+        remote: None,
+        worktree: None,
+        tip: None,
+    }
+}
+
+#[test]
+fn deletes_merged_branches() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.merged_branch("merged");
+        repo.branch("unmerged");
+
+        let broom = repo.builder_with(backend).branch("main").build();
+        let report = broom.scan().unwrap();
+        let outcomes = broom.apply(&report.merged).unwrap();
+
+        assert_eq!(outcomes.len(), 1, "{:?}", backend);
+        assert_eq!(outcomes[0].branch.name, "merged");
+        assert_eq!(outcomes[0].status, DeletionStatus::Deleted, "{:?}", backend);
+        assert!(!repo.has_branch("merged"), "{:?}", backend);
+        assert!(repo.has_branch("unmerged"), "{:?}", backend);
+    }
+}
+
+#[test]
+fn deletes_branches_merged_on_another_target() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.git(&["checkout", "--quiet", "-b", "develop"]);
+        repo.merged_branch("feature");
+        repo.git(&["checkout", "--quiet", "main"]);
+
+        let broom = repo.builder_with(backend).branch("develop").build();
+        let report = broom.scan().unwrap();
+        let outcomes = broom.apply(&report.merged).unwrap();
+
+        assert_eq!(outcomes[0].branch.name, "feature", "{:?}", backend);
+        assert_eq!(outcomes[0].status, DeletionStatus::Deleted, "{:?}", backend);
+        assert!(!repo.has_branch("feature"), "{:?}", backend);
+    }
+}
+
+#[test]
+fn keeps_unmerged_branches() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.branch("unmerged");
+
+        let outcomes = repo
+            .builder_with(backend)
+            .branch("main")
+            .build()
+            .apply(&[merged("unmerged")])
+            .unwrap();
+
+        match &outcomes[0].status {
+            DeletionStatus::Failed(e) => {
+                assert_eq!(e.kind, DeletionErrorKind::NotFullyMerged, "{:?}", backend);
+                assert!(e.message.contains("not fully merged"), "{:?}", backend);
+            }
+            status => panic!("{:?}: unexpected {:?}", backend, status),
+        }
+        assert!(repo.has_branch("unmerged"), "{:?}", backend);
+    }
+}
+
+#[test]
+fn keeps_branches_merged_by_content_which_changed_since_the_scan() {
+    let repo = TestRepo::new();
+    repo.squash_merged_branch("squashed");
+    repo.rebase_merged_branch("rebased");
+
+    let broom = repo
+        .builder()
+        .branch("main")
+        .squash_merged(true)
+        .rebase_merged(true)
+        .build();
+    let report = broom.scan().unwrap();
+    let squashed = report
+        .merged
+        .iter()
+        .find(|branch| branch.name == "squashed");
+    let rebased = report.merged.iter().find(|branch| branch.name == "rebased");
+    let (squashed, rebased) = (squashed.unwrap().clone(), rebased.unwrap().clone());
+
+    repo.git(&["checkout", "--quiet", "squashed"]);
+    repo.commit("after-the-scan.txt");
+    repo.git(&["checkout", "--quiet", "main"]);
+
+    let mut unknown_tip = rebased.clone();
+    unknown_tip.tip = None;
+
+    let outcomes = broom.apply(&[squashed, unknown_tip]).unwrap();
+
+    assert!(outcomes.iter().all(|outcome| matches!(
+        &outcome.status,
+        DeletionStatus::Failed(e) if e.kind == DeletionErrorKind::NotFullyMerged
+    )));
+    assert!(repo.has_branch("squashed"));
+    assert!(repo.has_branch("rebased"));
+
+    let outcomes = broom.apply(&[rebased]).unwrap();
+
+    assert_eq!(outcomes[0].status, DeletionStatus::Deleted);
+}
+
+#[test]
+fn tells_why_a_branch_is_not_deleted() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.merged_branch("checked-out");
+        repo.merged_branch("locked");
+        repo.worktree("checked-out-worktree", "checked-out");
+        std::fs::write(repo.path().join(".git/refs/heads/locked.lock"), "").unwrap();
+
+        let outcomes = repo
+            .builder_with(backend)
+            .branch("main")
+            .build()
+            .apply(&[merged("checked-out"), merged("locked"), merged("missing")])
+            .unwrap();
+
+        let kinds: Vec<Option<DeletionErrorKind>> = outcomes
+            .iter()
+            .map(|outcome| match &outcome.status {
+                DeletionStatus::Failed(e) => Some(e.kind),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            kinds,
+            [
+                Some(DeletionErrorKind::CheckedOut),
+                Some(DeletionErrorKind::RefLocked),
+                Some(DeletionErrorKind::NotFound),
+            ],
+            "{:?}",
+            backend
+        );
+        assert!(repo.has_branch("checked-out"), "{:?}", backend);
+        assert!(repo.has_branch("locked"), "{:?}", backend);
+    }
+}
+
+#[test]
+fn refuses_protected_branches() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.merged_branch("release/1.0");
+        repo.git(&["config", "broom.protectedbranches", "^release/"]);
+
+        let broom = repo.builder_with(backend).branch("main").build();
+        let report = broom.scan().unwrap();
+        let outcomes = broom.apply(&report.merged).unwrap();
+
+        assert_eq!(
+            outcomes[0].status,
+            DeletionStatus::Protected,
+            "{:?}",
+            backend
+        );
+        assert!(repo.has_branch("release/1.0"), "{:?}", backend);
+    }
+}
+
+#[test]
+fn deletes_protected_branches_when_included() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.merged_branch("release/1.0");
+        repo.git(&["config", "broom.protectedbranches", "^release/"]);
+
+        let broom = repo
+            .builder_with(backend)
+            .branch("main")
+            .include_protected_branches(true)
+            .build();
+        let report = broom.scan().unwrap();
+        let outcomes = broom.apply(&report.merged).unwrap();
+
+        assert_eq!(outcomes[0].status, DeletionStatus::Deleted, "{:?}", backend);
+        assert!(!repo.has_branch("release/1.0"), "{:?}", backend);
+    }
+}
+
+#[test]
+fn force_deletes_branches_whose_upstream_is_gone_only_when_asked_to() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.gone_branch("gone");
+
+        let broom = repo.builder_with(backend).branch("main").gone(true).build();
+        let report = broom.scan().unwrap();
+        let gone = report.gone.unwrap();
+        let outcomes = broom.apply(&gone).unwrap();
+
+        match &outcomes[0].status {
+            DeletionStatus::Failed(e) => {
+                assert_eq!(e.kind, DeletionErrorKind::NotFullyMerged, "{:?}", backend)
+            }
+            status => panic!("{:?}: unexpected status {:?}", backend, status),
+        }
+
+        let commits = broom.unmerged_commits(&gone[0], &report.targets).unwrap();
+        assert_eq!(commits.len(), 1, "{:?}", backend);
+        assert_eq!(commits[0].summary, "gone.txt", "{:?}", backend);
+        assert_eq!(Some(&commits[0].id), gone[0].tip.as_ref(), "{:?}", backend);
+
+        let outcome = broom.force_delete(&gone[0]).unwrap();
+
+        assert_eq!(outcome.status, DeletionStatus::Deleted, "{:?}", backend);
+        assert!(!repo.has_branch("gone"), "{:?}", backend);
+        assert_eq!(repo.config("branch.gone.remote"), None, "{:?}", backend);
+    }
+}
+
+#[test]
+fn deletes_merged_branches_from_a_remote() {
+    let repo = TestRepo::new();
+    repo.merged_branch("merged");
+    repo.merged_branch("release/1.0");
+    repo.branch("unmerged");
+    for branch in ["main", "merged", "release/1.0", "unmerged"] {
+        repo.push(branch);
+    }
+    repo.git(&["config", "broom.protectedremotebranches", "^release/"]);
+
+    let broom = repo.builder().branch("main").remote("origin").build();
+    let report = broom.scan().unwrap();
+    let names: Vec<&str> = report
+        .merged
+        .iter()
+        .map(|branch| branch.name.as_str())
+        .collect();
+
+    assert_eq!(report.remote.as_deref(), Some("origin"));
+    assert_eq!(report.targets, ["origin/main"]);
+    assert_eq!(names, ["origin/merged", "origin/release/1.0"]);
+    assert!(report.merged[1].protected);
+
+    let outcomes = broom.apply(&report.merged).unwrap();
+
+    assert_eq!(outcomes[0].status, DeletionStatus::Deleted);
+    assert_eq!(outcomes[1].status, DeletionStatus::Protected);
+    assert_eq!(
+        repo.git_origin(&["for-each-ref", "--format=%(refname:short)", "refs/heads"]),
+        "main\nrelease/1.0\nunmerged"
+    );
+    assert!(repo.has_branch("merged"));
+}
+
+#[test]
+fn never_proposes_the_default_branch_of_a_remote() {
+    let repo = TestRepo::new();
+    repo.push("main");
+    repo.git(&["remote", "set-head", "origin", "main"]);
+    repo.merged_branch("develop");
+    repo.push("develop");
+    repo.git(&["remote", "add", "upstream", repo.origin().to_str().unwrap()]);
+    repo.git_origin(&["branch", "--quiet", "merged", "develop"]);
+
+    let report = repo
+        .builder()
+        .branch("develop")
+        .remote("upstream")
+        .fetch(true)
+        .build()
+        .scan()
+        .unwrap();
+    let names: Vec<&str> = report
+        .merged
+        .iter()
+        .map(|branch| branch.name.as_str())
+        .collect();
+
+    assert!(report.notices.contains(&Notice::Fetched {
+        remote: String::from("upstream"),
+        pruned: Vec::new(),
+    }));
+    assert_eq!(names, ["upstream/main", "upstream/merged"]);
+
+    repo.git(&["remote", "set-head", "upstream", "main"]);
+    let report = repo
+        .builder()
+        .branch("develop")
+        .remote("upstream")
+        .build()
+        .scan()
+        .unwrap();
+    let names: Vec<&str> = report
+        .merged
+        .iter()
+        .map(|branch| branch.name.as_str())
+        .collect();
+
+    assert_eq!(names, ["upstream/merged"]);
+}
+
+#[test]
+fn deletes_stale_remote_tracking_branches() {
+    let repo = TestRepo::new();
+    repo.branch("removed");
+    repo.push("main");
+    repo.push("removed");
+    repo.git_origin(&["branch", "--quiet", "-D", "removed"]);
+    repo.git(&["update-ref", "refs/remotes/old/feature", "main"]);
+
+    let broom = repo.builder().branch("main").stale(true).build();
+    let report = broom.scan().unwrap();
+    let stale = report.stale.unwrap();
+    let statuses: Vec<(&str, &MergeStatus)> = stale
+        .iter()
+        .map(|branch| (branch.name.as_str(), &branch.status))
+        .collect();
+
+    assert_eq!(
+        statuses,
+        [
+            ("old/feature", &MergeStatus::RemoteRemoved),
+            ("origin/removed", &MergeStatus::StaleRemoteBranch),
+        ]
+    );
+
+    let outcomes = broom.apply(&stale).unwrap();
+
+    assert!(outcomes
+        .iter()
+        .all(|outcome| outcome.status == DeletionStatus::Deleted));
+    assert_eq!(repo.git(&["branch", "--remotes"]), "origin/main");
+    assert!(repo.has_branch("removed"));
+}
+
+#[test]
+fn never_force_deletes_protected_branches_unless_included() {
+    let repo = TestRepo::new();
+    repo.gone_branch("release/1.0");
+    repo.git(&["config", "broom.protectedbranches", "^release/"]);
+
+    let broom = repo.builder().branch("main").gone(true).build();
+    let gone = broom.scan().unwrap().gone.unwrap();
+    let outcome = broom.force_delete(&gone[0]).unwrap();
+
+    assert_eq!(outcome.status, DeletionStatus::Protected);
+    assert!(repo.has_branch("release/1.0"));
+}
+
+#[test]
+fn removes_clean_worktrees() {
+    let repo = TestRepo::new();
+    repo.merged_branch("merged");
+    let worktree = repo.worktree("merged-worktree", "merged");
+
+    let broom = repo
+        .builder_with(BackendKind::Command)
+        .branch("main")
+        .build();
+    let report = broom.scan().unwrap();
+    let outcomes = broom.apply(&report.merged).unwrap();
+
+    assert_eq!(outcomes[0].status, DeletionStatus::Deleted);
+    assert_eq!(outcomes[0].removed_worktree, Some(worktree.clone()));
+    assert!(!worktree.exists());
+    assert!(!repo.has_branch("merged"));
+}
+
+#[test]
+fn keeps_the_worktree_of_a_branch_git_refuses_to_delete() {
+    let repo = TestRepo::new();
+    repo.merged_branch("merged");
+    let worktree = repo.worktree("merged-worktree", "merged");
+
+    let broom = repo
+        .builder_with(BackendKind::Command)
+        .branch("main")
+        .build();
+    let report = broom.scan().unwrap();
+
+    std::fs::write(worktree.join("new.txt"), "new").unwrap();
+    repo.git(&["-C", worktree.to_str().unwrap(), "add", "new.txt"]);
+    repo.git(&[
+        "-C",
+        worktree.to_str().unwrap(),
+        "commit",
+        "--quiet",
+        "-m",
+        "new",
+    ]);
+
+    let outcomes = broom.apply(&report.merged).unwrap();
+
+    match &outcomes[0].status {
+        DeletionStatus::Failed(e) => assert_eq!(e.kind, DeletionErrorKind::NotFullyMerged),
+        status => panic!("unexpected {:?}", status),
+    }
+    assert_eq!(outcomes[0].removed_worktree, None);
+    assert!(worktree.exists());
+    assert!(repo.has_branch("merged"));
+}
+
+#[test]
+fn keeps_worktrees_with_local_changes() {
+    let repo = TestRepo::new();
+    repo.merged_branch("merged");
+    let worktree = repo.worktree("merged-worktree", "merged");
+    std::fs::write(worktree.join("work-in-progress"), "").unwrap();
+
+    let broom = repo
+        .builder_with(BackendKind::Command)
+        .branch("main")
+        .build();
+    let report = broom.scan().unwrap();
+    let outcomes = broom.apply(&report.merged).unwrap();
+
+    assert_eq!(outcomes[0].status, DeletionStatus::WorktreeNotClean);
+    assert_eq!(outcomes[0].removed_worktree, None);
+    assert!(worktree.exists());
+    assert!(repo.has_branch("merged"));
+}
+
+#[test]
+fn gitoxide_backend_keeps_branches_checked_out_in_worktrees() {
+    let repo = TestRepo::new();
+    repo.merged_branch("merged");
+    repo.merged_branch("other");
+    let worktree = repo.worktree("merged-worktree", "merged");
+
+    let broom = repo
+        .builder_with(BackendKind::Gitoxide)
+        .branch("main")
+        .build();
+    let report = broom.scan().unwrap();
+    let outcomes = broom.apply(&report.merged).unwrap();
+
+    match &outcomes[0].status {
+        DeletionStatus::Failed(e) => assert_eq!(e.kind, DeletionErrorKind::CheckedOut),
+        status => panic!("unexpected {:?}", status),
+    }
+    assert_eq!(outcomes[1].status, DeletionStatus::Deleted);
+    assert!(repo.has_branch("merged"));
+    assert!(!repo.has_branch("other"));
+    assert!(worktree.exists());
+}
@@ -0,0 +1,191 @@
+/*
+Git Broom
+Copyright (C) 2024  All contributors.
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+mod common;
+
+use common::{TestRepo, BACKENDS};
+use git_broom::DeletionStatus;
+
+#[test]
+fn archives_deleted_branches() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.merged_branch("merged");
+        let tip = repo.git(&["rev-parse", "merged"]);
+
+        let broom = repo.builder_with(backend).branch("main").build();
+        let report = broom.scan().unwrap();
+        let outcomes = broom.apply(&report.merged).unwrap();
+
+        assert_eq!(outcomes[0].status, DeletionStatus::Deleted, "{:?}", backend);
+
+        let archived = broom.archived_branches().unwrap();
+
+        assert_eq!(archived.len(), 1, "{:?}", backend);
+        assert_eq!(archived[0].name, "merged", "{:?}", backend);
+        assert_eq!(archived[0].tip, tip, "{:?}", backend);
+        assert_eq!(archived[0].age_in_days(), 0, "{:?}", backend);
+        assert_eq!(
+            outcomes[0].archived.as_ref(),
+            Some(&archived[0].reference),
+            "{:?}",
+            backend
+        );
+        assert_eq!(repo.git(&["rev-parse", &archived[0].reference]), tip);
+    }
+}
+
+#[test]
+fn does_not_archive_when_disabled() {
+    let repo = TestRepo::new();
+    repo.merged_branch("merged");
+    repo.merged_branch("other");
+    repo.git(&["config", "broom.archive", "false"]);
+
+    let broom = repo.builder().branch("main").build();
+    let report = broom.scan().unwrap();
+    let outcomes = broom.apply(&report.merged).unwrap();
+
+    assert_eq!(outcomes[0].archived, None);
+    assert!(broom.archived_branches().unwrap().is_empty());
+
+    let broom = repo.builder().branch("main").archive(false).build();
+    repo.git(&["config", "broom.archive", "true"]);
+    broom.apply(&report.merged[1..]).unwrap();
+
+    assert!(broom.archived_branches().unwrap().is_empty());
+}
+
+#[test]
+fn does_not_keep_the_archive_of_branches_which_are_not_deleted() {
+    let repo = TestRepo::new();
+    repo.gone_branch("gone");
+
+    let broom = repo.builder().branch("main").gone(true).build();
+    let gone = broom.scan().unwrap().gone.unwrap();
+    let outcomes = broom.apply(&gone).unwrap();
+
+    assert!(matches!(outcomes[0].status, DeletionStatus::Failed(_)));
+    assert!(broom.archived_branches().unwrap().is_empty());
+}
+
+#[test]
+fn purges_archived_branches_older_than_the_retention_period() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.git(&[
+            "update-ref",
+            "refs/broom/archive/2020-02-29/1/heads/old",
+            "HEAD",
+        ]);
+        repo.git(&[
+            "update-ref",
+            "refs/broom/archive/2999-01-01/1/remotes/origin/future",
+            "HEAD",
+        ]);
+        repo.git(&[
+            "update-ref",
+            "refs/broom/archive/2023-02-30/1/heads/invalid",
+            "HEAD",
+        ]);
+        repo.git(&[
+            "update-ref",
+            "refs/broom/archive/2020-02-29/1/other",
+            "HEAD",
+        ]);
+
+        let broom = repo.builder_with(backend).build();
+
+        let names: Vec<String> = broom
+            .archived_branches()
+            .unwrap()
+            .into_iter()
+            .map(|archived| archived.name)
+            .collect();
+        assert_eq!(names, ["old", "origin/future"], "{:?}", backend);
+
+        let purged = broom.purge_archive(30).unwrap();
+
+        assert_eq!(purged.len(), 1, "{:?}", backend);
+        assert_eq!(purged[0].date, "2020-02-29", "{:?}", backend);
+        assert_eq!(broom.archived_branches().unwrap().len(), 1, "{:?}", backend);
+    }
+}
+
+#[test]
+fn keeps_a_separate_archive_of_each_deletion() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.commit("second");
+        let broom = repo.builder_with(backend).branch("main").build();
+
+        // A branch named like the directory of an archived one, then the same name again.
+        for name in ["feat/x", "feat", "feat/x"] {
+            repo.git(&["branch", name, "main~1"]);
+            let report = broom.scan().unwrap();
+            let outcomes = broom.apply(&report.merged).unwrap();
+
+            assert_eq!(outcomes.len(), 1, "{:?}", backend);
+            assert_eq!(outcomes[0].status, DeletionStatus::Deleted, "{:?}", backend);
+        }
+
+        let mut archived: Vec<(String, bool)> = broom
+            .archived_branches()
+            .unwrap()
+            .into_iter()
+            .map(|archived| (archived.name, archived.remote))
+            .collect();
+        archived.sort();
+
+        assert_eq!(
+            archived,
+            [
+                (String::from("feat"), false),
+                (String::from("feat/x"), false),
+                (String::from("feat/x"), false),
+            ],
+            "{:?}",
+            backend
+        );
+    }
+}
+
+#[test]
+fn keeps_a_branch_whose_tip_cannot_be_archived() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.merged_branch("merged");
+        repo.merged_branch("other");
+        // A reference in the way of the archive namespace.
+        repo.git(&["update-ref", "refs/broom/archive", "HEAD"]);
+
+        let broom = repo.builder_with(backend).branch("main").build();
+        let report = broom.scan().unwrap();
+        let outcomes = broom.apply(&report.merged).unwrap();
+
+        assert_eq!(outcomes.len(), 2, "{:?}", backend);
+        for outcome in &outcomes {
+            assert!(
+                matches!(outcome.status, DeletionStatus::Error(_)),
+                "{:?}",
+                backend
+            );
+        }
+        assert!(repo.has_branch("merged"), "{:?}", backend);
+        assert!(repo.has_branch("other"), "{:?}", backend);
+    }
+}
@@ -0,0 +1,80 @@
+/*
+Git Broom
+Copyright (C) 2024  All contributors.
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+mod common;
+
+use common::{BackendKind, TestRepo};
+use git_broom::Error;
+
+#[test]
+fn saves_branches_to_a_verified_bundle() {
+    let repo = TestRepo::new();
+    repo.merged_branch("merged");
+    let tip = repo.git(&["rev-parse", "merged"]);
+    let path = repo.sibling("branches.bundle");
+
+    let broom = repo.builder().branch("main").build();
+    let report = broom.scan().unwrap();
+    let bundle = broom.bundle(&path, &report.merged).unwrap();
+
+    assert_eq!(bundle.references, ["refs/heads/merged"]);
+    assert_eq!(
+        repo.git(&["bundle", "list-heads", bundle.path.to_str().unwrap()]),
+        format!("{} refs/heads/merged", tip)
+    );
+
+    broom.apply(&report.merged).unwrap();
+    let entry = broom.restore("merged").unwrap();
+
+    assert_eq!(
+        entry.bundle,
+        Some(bundle.path.to_string_lossy().into_owned())
+    );
+}
+
+#[test]
+fn fails_when_the_bundle_cannot_be_written() {
+    let repo = TestRepo::new();
+    repo.merged_branch("merged");
+    let path = repo.sibling("missing").join("branches.bundle");
+
+    let broom = repo.builder().branch("main").build();
+    let report = broom.scan().unwrap();
+
+    assert!(matches!(
+        broom.bundle(&path, &report.merged),
+        Err(Error::Bundle { .. })
+    ));
+    assert!(!path.exists());
+}
+
+#[test]
+fn bundles_are_not_supported_by_gitoxide() {
+    let repo = TestRepo::new();
+    repo.merged_branch("merged");
+
+    let broom = repo
+        .builder_with(BackendKind::Gitoxide)
+        .branch("main")
+        .build();
+    let report = broom.scan().unwrap();
+
+    match broom.bundle(repo.sibling("branches.bundle"), &report.merged) {
+        Err(Error::Bundle { source, .. }) => assert!(matches!(*source, Error::Unsupported)),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
@@ -0,0 +1,335 @@
+/*
+Git Broom
+Copyright (C) 2024  All contributors.
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+mod common;
+
+use std::ffi::{OsStr, OsString};
+use std::process::{Command, Output, Stdio};
+
+use common::{isolated, TestRepo};
+
+fn git_broom<S: AsRef<OsStr>>(repo: &TestRepo, args: &[S]) -> Output {
+    isolated(Command::new(env!("CARGO_BIN_EXE_git-broom")))
+        .args(args)
+        .arg(repo.path())
+        .env("LANG", "en_US.UTF-8")
+        .env("NO_COLOR", "1")
+        .stdin(Stdio::null())
+        .output()
+        .expect("cannot run git-broom")
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn refuses_to_prompt_without_a_terminal() {
+    let repo = TestRepo::new();
+    repo.merged_branch("merged");
+
+    let output = git_broom(&repo, &["--branch", "main"]);
+
+    assert!(stdout(&output).contains("standard input is not a terminal"));
+    assert!(repo.has_branch("merged"));
+}
+
+#[test]
+fn deletes_without_asking() {
+    let repo = TestRepo::new();
+    repo.merged_branch("merged");
+    repo.merged_branch("other");
+    repo.branch("unmerged");
+
+    let output = git_broom(&repo, &["--branch", "main", "--yes"]);
+
+    assert!(stdout(&output).contains("Branch merged deleted."));
+    assert!(!repo.has_branch("merged"));
+    assert!(!repo.has_branch("other"));
+    assert!(repo.has_branch("unmerged"));
+}
+
+#[test]
+fn deletes_selected_branches_only() {
+    let repo = TestRepo::new();
+    repo.merged_branch("feature/one");
+    repo.merged_branch("feature/two");
+    repo.merged_branch("other");
+
+    let output = git_broom(
+        &repo,
+        &["--branch", "main", "--yes", "--select", "^feature/"],
+    );
+
+    assert!(stdout(&output).contains("other (not selected)"));
+    assert!(!repo.has_branch("feature/one"));
+    assert!(!repo.has_branch("feature/two"));
+    assert!(repo.has_branch("other"));
+}
+
+#[test]
+fn prints_json() {
+    let repo = TestRepo::new();
+    repo.merged_branch("merged");
+    repo.merged_branch("release/1.0");
+    repo.git(&["config", "broom.protectedbranches", "^release/"]);
+
+    let output = git_broom(&repo, &["--branch", "main", "--format", "json"]);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert_eq!(json["targets"], serde_json::json!(["main"]));
+    assert_eq!(json["branches"][0]["name"], "merged");
+    assert_eq!(json["branches"][0]["category"], "merged");
+    assert_eq!(json["branches"][0]["reason"], "merged");
+    assert_eq!(json["branches"][0]["protected"], false);
+    assert_eq!(
+        json["branches"][0]["tip"],
+        repo.git(&["rev-parse", "merged"])
+    );
+    assert_eq!(json["branches"][1]["name"], "release/1.0");
+    assert_eq!(json["branches"][1]["protected"], true);
+    assert_eq!(json["branches"][1]["selected"], false);
+    assert_eq!(json["outcomes"], serde_json::json!([]));
+    assert!(repo.has_branch("merged"));
+}
+
+#[test]
+fn prints_json_outcomes() {
+    let repo = TestRepo::new();
+    repo.merged_branch("merged");
+
+    let output = git_broom(&repo, &["--branch", "main", "--format", "json", "--yes"]);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert_eq!(json["outcomes"][0]["name"], "merged");
+    assert_eq!(json["outcomes"][0]["status"], "deleted");
+    assert!(!repo.has_branch("merged"));
+}
+
+#[cfg(unix)]
+#[test]
+fn prints_json_with_a_worktree_path_which_is_not_utf8() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let repo = TestRepo::new();
+    repo.merged_branch("merged");
+    let mut worktree = repo.sibling("worktree").into_os_string();
+    worktree.push(OsStr::from_bytes(b"\xff"));
+    let status = isolated(Command::new("git"))
+        .current_dir(repo.path())
+        .args(["worktree", "add", "--quiet"])
+        .arg(&worktree)
+        .arg("merged")
+        .status()
+        .expect("cannot run git");
+    assert!(status.success());
+
+    let output = git_broom(
+        &repo,
+        &[
+            "--branch",
+            "main",
+            "--format",
+            "json",
+            "--backend",
+            "gitoxide",
+        ],
+    );
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert_eq!(json["branches"][0]["name"], "merged");
+    assert!(json["branches"][0]["worktree"]
+        .as_str()
+        .unwrap()
+        .ends_with("worktree\u{fffd}"));
+}
+
+#[test]
+fn prints_porcelain_records() {
+    let repo = TestRepo::new();
+    repo.merged_branch("merged");
+    repo.merged_branch("release/1.0");
+    repo.git(&["config", "broom.protectedbranches", "^release/"]);
+
+    let output = git_broom(&repo, &["--branch", "main", "--porcelain", "-z"]);
+    let records: Vec<Vec<String>> = stdout(&output)
+        .split_terminator('\0')
+        .map(|record| record.split('\t').map(String::from).collect())
+        .collect();
+
+    assert_eq!(
+        records,
+        [
+            vec![
+                String::from("merged"),
+                String::from("merged"),
+                String::from("merged"),
+                String::from("unprotected"),
+                String::from("selected"),
+                repo.git(&["rev-parse", "merged"]),
+                String::from("main"),
+                String::new(),
+            ],
+            vec![
+                String::from("release/1.0"),
+                String::from("merged"),
+                String::from("merged"),
+                String::from("protected"),
+                String::from("unselected"),
+                repo.git(&["rev-parse", "release/1.0"]),
+                String::from("main"),
+                String::new(),
+            ],
+        ]
+    );
+    assert!(repo.has_branch("merged"));
+}
+
+#[test]
+fn checks_for_merged_branches() {
+    let repo = TestRepo::new();
+
+    assert_eq!(git_broom(&repo, &["--check"]).status.code(), Some(0));
+
+    repo.merged_branch("release/1.0");
+    repo.git(&["config", "broom.protectedbranches", "^release/"]);
+
+    assert_eq!(git_broom(&repo, &["--check"]).status.code(), Some(4));
+
+    repo.merged_branch("merged");
+
+    assert_eq!(git_broom(&repo, &["--check"]).status.code(), Some(3));
+    assert_eq!(
+        git_broom(&repo, &["--check", "--max-branches", "1"])
+            .status
+            .code(),
+        Some(0)
+    );
+    assert_eq!(
+        git_broom(&repo, &["--check", "--select", "^feature/"])
+            .status
+            .code(),
+        Some(0)
+    );
+    assert_eq!(
+        git_broom(&repo, &["--check", "--select", "^release/"])
+            .status
+            .code(),
+        Some(4)
+    );
+    assert!(repo.has_branch("merged"));
+}
+
+#[test]
+fn exits_with_an_error_on_failure() {
+    let repo = TestRepo::new();
+    repo.merged_branch("merged");
+
+    assert_eq!(
+        git_broom(&repo, &["--branch", "main"]).status.code(),
+        Some(1)
+    );
+    assert_eq!(
+        git_broom(&repo, &["--branch", "missing"]).status.code(),
+        Some(1)
+    );
+}
+
+#[test]
+fn refuses_a_bundle_in_json_without_deleting() {
+    let repo = TestRepo::new();
+    repo.merged_branch("merged");
+    let bundle = repo.sibling("branches.bundle");
+    let bundle = bundle.to_str().unwrap();
+
+    let output = git_broom(&repo, &["--format", "json", "--bundle", bundle]);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(!repo.sibling("branches.bundle").exists());
+
+    let output = git_broom(&repo, &["--format", "json", "--bundle", bundle, "--yes"]);
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(repo.sibling("branches.bundle").exists());
+    assert!(!repo.has_branch("merged"));
+}
+
+#[cfg(unix)]
+#[test]
+fn prints_json_with_a_bundle_path_which_is_not_utf8() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let repo = TestRepo::new();
+    repo.merged_branch("merged");
+    let mut bundle = repo.sibling("branches").into_os_string();
+    bundle.push(OsStr::from_bytes(b"\xff.bundle"));
+
+    let output = git_broom(
+        &repo,
+        &[
+            OsString::from("--format"),
+            OsString::from("json"),
+            OsString::from("--yes"),
+            OsString::from("--bundle"),
+            bundle,
+        ],
+    );
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert!(json["bundle"]["path"]
+        .as_str()
+        .unwrap()
+        .ends_with("branches\u{fffd}.bundle"));
+    assert_eq!(json["outcomes"][0]["name"], "merged");
+    assert_eq!(json["outcomes"][0]["status"], "deleted");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(!repo.has_branch("merged"));
+}
+
+#[test]
+fn deletes_nothing_when_the_bundle_cannot_be_written() {
+    let repo = TestRepo::new();
+    repo.merged_branch("merged");
+    let path = repo.sibling("missing").join("branches.bundle");
+
+    let output = git_broom(
+        &repo,
+        &[
+            "--branch",
+            "main",
+            "--yes",
+            "--bundle",
+            path.to_str().unwrap(),
+        ],
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains("no branch was deleted"));
+    assert!(repo.has_branch("merged"));
+}
+
+#[test]
+fn never_deletes_protected_branches_without_asking() {
+    let repo = TestRepo::new();
+    repo.merged_branch("release/1.0");
+    repo.git(&["config", "broom.protectedbranches", "^release/"]);
+
+    git_broom(&repo, &["--branch", "main", "--yes"]);
+
+    assert!(repo.has_branch("release/1.0"));
+}
@@ -0,0 +1,264 @@
+/*
+Git Broom
+Copyright (C) 2024  All contributors.
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Throwaway repositories with scripted branch histories.
+
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Once;
+
+use git_broom::backend::{CommandBackend, GixBackend};
+use git_broom::{GitBroom, GitBroomBuilder};
+use tempfile::TempDir;
+
+/// The backends the scenarios supported by every backend run with.
+pub const BACKENDS: [BackendKind; 2] = [BackendKind::Command, BackendKind::Gitoxide];
+
+#[derive(Clone, Copy, Debug)]
+pub enum BackendKind {
+    Command,
+    Gitoxide,
+}
+
+static ISOLATE: Once = Once::new();
+
+/// Keeps the system and global Git configuration of the machine running the tests out of the
+/// library, which reads it through the environment of the test process, both for Git and for
+/// gitoxide.
+///
+/// Every test calls it before anything else, directly or through [`TestRepo::new`], so that the
+/// environment is never changed while another test reads it. The commands spawned by the tests
+/// are isolated on their own with [`isolated`].
+pub fn isolate() {
+    ISOLATE.call_once(|| {
+        std::env::set_var("GIT_CONFIG_NOSYSTEM", "1");
+        std::env::set_var("GIT_CONFIG_GLOBAL", "/dev/null");
+        std::env::remove_var("GIT_DIR");
+        std::env::remove_var("GIT_WORK_TREE");
+    });
+}
+
+/// A repository in a temporary directory, deleted when dropped.
+pub struct TestRepo {
+    directory: TempDir,
+}
+
+impl TestRepo {
+    /// Creates a repository with an initial commit on `main`.
+    pub fn new() -> Self {
+        isolate();
+
+        let repo = Self {
+            directory: TempDir::new().expect("cannot create a temporary directory"),
+        };
+
+        repo.git(&["init", "--quiet", "--initial-branch=main", "repo"]);
+        repo.commit("initial");
+
+        repo
+    }
+
+    /// The path of the main worktree.
+    pub fn path(&self) -> PathBuf {
+        self.directory.path().join("repo")
+    }
+
+    /// A path next to the repository, for worktrees and remotes.
+    pub fn sibling(&self, name: &str) -> PathBuf {
+        self.directory.path().join(name)
+    }
+
+    /// Runs Git in the main worktree, panicking if it fails, and returns its trimmed output.
+    pub fn git(&self, args: &[&str]) -> String {
+        let directory = if self.path().exists() {
+            self.path()
+        } else {
+            self.directory.path().to_path_buf()
+        };
+
+        run_git(&directory, args)
+    }
+
+    /// Commits a new file on the current branch.
+    pub fn commit(&self, name: &str) {
+        std::fs::write(self.path().join(name), name).expect("cannot write a file");
+        self.git(&["add", name]);
+        self.git(&["commit", "--quiet", "-m", name]);
+    }
+
+    /// Creates a branch from the current one, with a commit of its own, then goes back.
+    pub fn branch(&self, name: &str) {
+        let current = self.git(&["symbolic-ref", "--short", "HEAD"]);
+
+        self.git(&["checkout", "--quiet", "-b", name]);
+        self.commit(&format!("{}.txt", name.replace('/', "-")));
+        self.git(&["checkout", "--quiet", &current]);
+    }
+
+    /// Creates a branch, and merges it on the current one.
+    pub fn merged_branch(&self, name: &str) {
+        self.branch(name);
+        self.git(&["merge", "--quiet", "--no-ff", "--no-edit", name]);
+    }
+
+    /// Creates a branch with two commits, and lands them on the current one as a single commit.
+    pub fn squash_merged_branch(&self, name: &str) {
+        self.branch(name);
+        self.git(&["checkout", "--quiet", name]);
+        self.commit(&format!("{}-2.txt", name.replace('/', "-")));
+        self.git(&["checkout", "--quiet", "-"]);
+        self.git(&["merge", "--quiet", "--squash", name]);
+        self.git(&["commit", "--quiet", "-m", &format!("Squashed {}", name)]);
+    }
+
+    /// Creates a branch with two commits, and copies them on the current one, once it has moved on,
+    /// as rebasing the branch before merging it does.
+    pub fn rebase_merged_branch(&self, name: &str) {
+        self.branch(name);
+        self.git(&["checkout", "--quiet", name]);
+        self.commit(&format!("{}-2.txt", name.replace('/', "-")));
+        self.git(&["checkout", "--quiet", "-"]);
+        self.commit(&format!("before-{}.txt", name.replace('/', "-")));
+        self.git(&["cherry-pick", &format!("HEAD..{}", name)]);
+    }
+
+    /// Adds a linked worktree next to the repository, with a branch checked out.
+    pub fn worktree(&self, name: &str, branch: &str) -> PathBuf {
+        let path = self.sibling(name);
+
+        self.git(&["worktree", "add", "--quiet", path.to_str().unwrap(), branch]);
+
+        path.canonicalize().unwrap()
+    }
+
+    /// Adds a bare remote named `origin` next to the repository, unless it exists, and returns its
+    /// path.
+    pub fn origin(&self) -> PathBuf {
+        let remote = self.sibling("origin.git");
+
+        if !remote.exists() {
+            run_git(
+                self.directory.path(),
+                &["init", "--quiet", "--bare", "origin.git"],
+            );
+            self.git(&["remote", "add", "origin", remote.to_str().unwrap()]);
+        }
+
+        remote
+    }
+
+    /// Runs Git in the `origin` remote, panicking if it fails, and returns its trimmed output.
+    pub fn git_origin(&self, args: &[&str]) -> String {
+        run_git(&self.origin(), args)
+    }
+
+    /// Pushes a branch to `origin`, tracked by the local branch of the same name.
+    pub fn push(&self, name: &str) {
+        self.origin();
+        self.git(&["push", "--quiet", "--set-upstream", "origin", name]);
+    }
+
+    /// Adds a bare remote named `origin`, with a pushed branch tracked by the local branch of the
+    /// same name, then deletes it from the remote so that its upstream is gone.
+    pub fn gone_branch(&self, name: &str) {
+        self.branch(name);
+        self.push(name);
+        self.git_origin(&["branch", "--quiet", "-D", name]);
+        self.git(&["fetch", "--quiet", "--prune", "origin"]);
+    }
+
+    pub fn has_branch(&self, name: &str) -> bool {
+        git_command()
+            .current_dir(self.path())
+            .args(["rev-parse", "--verify", "--quiet"])
+            .arg(format!("refs/heads/{}", name))
+            .output()
+            .expect("cannot run git")
+            .status
+            .success()
+    }
+
+    /// Reads a value of the local configuration.
+    pub fn config(&self, key: &str) -> Option<String> {
+        let output = git_command()
+            .current_dir(self.path())
+            .args(["config", "--get", key])
+            .output()
+            .expect("cannot run git");
+
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .filter(|_| output.status.success())
+    }
+
+    pub fn builder(&self) -> GitBroomBuilder {
+        GitBroom::builder().repository(self.path())
+    }
+
+    /// A builder running the given backend against the repository.
+    pub fn builder_with(&self, backend: BackendKind) -> GitBroomBuilder {
+        builder_from(backend, &self.path())
+    }
+}
+
+/// A builder running the given backend from a directory, such as a subdirectory of a worktree or
+/// a bare repository, from which the repository is discovered.
+pub fn builder_from(backend: BackendKind, directory: &Path) -> GitBroomBuilder {
+    let builder = GitBroom::builder().repository(directory);
+
+    match backend {
+        BackendKind::Command => builder.backend(CommandBackend::new(Some(directory.to_path_buf()))),
+        BackendKind::Gitoxide => builder.backend(GixBackend::new(directory)),
+    }
+}
+
+/// Isolates a command from the Git configuration and repository of the machine running the tests.
+pub fn isolated(mut command: Command) -> Command {
+    command
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env("GIT_CONFIG_GLOBAL", "/dev/null")
+        .env_remove("GIT_DIR")
+        .env_remove("GIT_WORK_TREE");
+    command
+}
+
+fn git_command() -> Command {
+    isolated(Command::new("git"))
+}
+
+fn run_git(directory: &Path, args: &[&str]) -> String {
+    let output = git_command()
+        .current_dir(directory)
+        .args(args)
+        .env("GIT_AUTHOR_NAME", "Git Broom")
+        .env("GIT_AUTHOR_EMAIL", "git-broom@localhost")
+        .env("GIT_COMMITTER_NAME", "Git Broom")
+        .env("GIT_COMMITTER_EMAIL", "git-broom@localhost")
+        .output()
+        .expect("cannot run git");
+
+    assert!(
+        output.status.success(),
+        "git {} failed: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
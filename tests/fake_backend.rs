@@ -0,0 +1,326 @@
+/*
+Git Broom
+Copyright (C) 2024  All contributors.
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+mod common;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use git_broom::backend::LocalBranch;
+use git_broom::{Backend, DeletionError, DeletionErrorKind, DeletionStatus, Error, GitBroom};
+use tempfile::TempDir;
+
+/// A repository made of branch names, where a branch is merged on the branches listed along with
+/// it, and of other references such as archived branches.
+#[derive(Clone)]
+struct FakeBackend {
+    git_dir: PathBuf,
+    head: String,
+    branches: Arc<Mutex<Vec<LocalBranch>>>,
+    merged: BTreeSet<(String, String)>,
+    /// A branch which cannot be deleted, as if its reference could not be written.
+    failing: Option<String>,
+    references: Arc<Mutex<BTreeMap<String, String>>>,
+}
+
+impl FakeBackend {
+    fn new(git_dir: &TempDir, head: &str) -> Self {
+        std::fs::write(git_dir.path().join("config"), "").unwrap();
+
+        Self {
+            git_dir: git_dir.path().to_path_buf(),
+            head: head.to_string(),
+            branches: Arc::default(),
+            merged: BTreeSet::new(),
+            failing: None,
+            references: Arc::default(),
+        }
+    }
+
+    fn branch(mut self, name: &str, merged_on: &[&str]) -> Self {
+        self.branches.lock().unwrap().push(LocalBranch {
+            name: name.to_string(),
+            ..Default::default()
+        });
+
+        for target in merged_on {
+            self.merged.insert((name.to_string(), target.to_string()));
+        }
+
+        self
+    }
+
+    fn failing(mut self, name: &str) -> Self {
+        self.failing = Some(name.to_string());
+        self
+    }
+
+    fn branch_names(&self) -> Vec<String> {
+        self.branches
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|branch| branch.name.clone())
+            .collect()
+    }
+
+    fn exists(&self, revision: &str) -> bool {
+        let name = revision.strip_prefix("refs/heads/").unwrap_or(revision);
+
+        self.branch_names().iter().any(|branch| branch.eq(name))
+    }
+}
+
+impl Backend for FakeBackend {
+    fn git_directories(&self) -> Result<(PathBuf, PathBuf), Error> {
+        Ok((self.git_dir.clone(), self.git_dir.clone()))
+    }
+
+    fn head(&self) -> Result<Option<String>, Error> {
+        Ok(Some(format!("refs/heads/{}", self.head)))
+    }
+
+    fn remotes(&self) -> Result<Vec<String>, Error> {
+        Ok(Vec::new())
+    }
+
+    fn local_branches(&self) -> Result<Vec<LocalBranch>, Error> {
+        Ok(self.branches.lock().unwrap().clone())
+    }
+
+    fn remote_tracking_branches(&self) -> Result<Vec<String>, Error> {
+        Ok(Vec::new())
+    }
+
+    fn symbolic_ref_target(&self, _name: &str) -> Result<Option<String>, Error> {
+        Ok(None)
+    }
+
+    fn resolve_commit(&self, revision: &str) -> Result<Option<String>, Error> {
+        Ok(Some(revision.to_string()).filter(|revision| self.exists(revision)))
+    }
+
+    fn is_ancestor(&self, ancestor: &str, descendant: &str) -> Result<bool, Error> {
+        let ancestor = ancestor.strip_prefix("refs/heads/").unwrap_or(ancestor);
+        let descendant = descendant.strip_prefix("refs/heads/").unwrap_or(descendant);
+
+        Ok(ancestor == descendant
+            || self
+                .merged
+                .contains(&(ancestor.to_string(), descendant.to_string())))
+    }
+
+    fn count_commits(&self, _from: &str, _to: &str) -> Result<usize, Error> {
+        Ok(0)
+    }
+
+    fn worktrees(&self) -> Result<Vec<(PathBuf, String)>, Error> {
+        Ok(vec![(self.git_dir.clone(), self.head.clone())])
+    }
+
+    fn delete_branch(&self, name: &str, force: bool) -> Result<(), Error> {
+        if name == self.head {
+            return Err(DeletionError::new(DeletionErrorKind::CheckedOut, "checked out").into());
+        }
+
+        if !force && !self.is_ancestor(name, &self.head)? {
+            return Err(DeletionError::new(DeletionErrorKind::NotFullyMerged, "not merged").into());
+        }
+
+        if self.failing.as_deref() == Some(name) {
+            return Err(Error::backend("cannot write the reference"));
+        }
+
+        let mut branches = self.branches.lock().unwrap();
+        let count = branches.len();
+        branches.retain(|branch| branch.name != name);
+
+        if branches.len() < count {
+            Ok(())
+        } else {
+            Err(DeletionError::new(DeletionErrorKind::NotFound, "not found").into())
+        }
+    }
+
+    fn delete_remote_tracking_branch(&self, name: &str) -> Result<(), Error> {
+        Err(DeletionError::new(DeletionErrorKind::NotFound, name).into())
+    }
+
+    fn set_upstream(&self, _name: &str, _remote: &str, _merge: &str) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn references(&self, prefix: &str) -> Result<Vec<(String, String)>, Error> {
+        Ok(self
+            .references
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(name, _)| name.starts_with(prefix))
+            .map(|(name, id)| (name.clone(), id.clone()))
+            .collect())
+    }
+
+    fn set_reference(&self, name: &str, id: &str) -> Result<(), Error> {
+        self.references
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), id.to_string());
+        Ok(())
+    }
+
+    fn delete_reference(&self, name: &str) -> Result<(), Error> {
+        self.references.lock().unwrap().remove(name);
+        Ok(())
+    }
+}
+
+#[test]
+fn scans_and_deletes_through_the_backend() {
+    common::isolate();
+    let git_dir = TempDir::new().unwrap();
+    let backend = FakeBackend::new(&git_dir, "main")
+        .branch("main", &[])
+        .branch("develop", &[])
+        .branch("merged", &["main"])
+        .branch("merged-on-develop", &["develop"])
+        .branch("unmerged", &[]);
+
+    let broom = GitBroom::builder()
+        .backend(backend.clone())
+        .branches(["main", "develop"])
+        .build();
+
+    let report = broom.scan().unwrap();
+    let merged: Vec<&str> = report
+        .merged
+        .iter()
+        .map(|branch| branch.name.as_str())
+        .collect();
+
+    assert_eq!(report.targets, ["main", "develop"]);
+    assert_eq!(merged, ["merged", "merged-on-develop"]);
+
+    let outcomes = broom.apply(&report.merged).unwrap();
+
+    assert!(outcomes
+        .iter()
+        .all(|outcome| outcome.status == DeletionStatus::Deleted));
+    assert_eq!(backend.branch_names(), ["main", "develop", "unmerged"]);
+
+    let archived: Vec<String> = broom
+        .archived_branches()
+        .unwrap()
+        .into_iter()
+        .map(|archived| archived.name)
+        .collect();
+
+    assert_eq!(archived, ["merged", "merged-on-develop"]);
+}
+
+#[test]
+fn tells_what_happened_to_every_branch_when_one_fails() {
+    common::isolate();
+    let git_dir = TempDir::new().unwrap();
+    let backend = FakeBackend::new(&git_dir, "main")
+        .branch("main", &[])
+        .branch("first", &["main"])
+        .branch("failing", &["main"])
+        .branch("last", &["main"])
+        .failing("failing");
+
+    let broom = GitBroom::builder()
+        .backend(backend.clone())
+        .branch("main")
+        .build();
+
+    let report = broom.scan().unwrap();
+    let outcomes = broom.apply(&report.merged).unwrap();
+    let statuses: Vec<&DeletionStatus> = outcomes.iter().map(|outcome| &outcome.status).collect();
+
+    assert_eq!(
+        statuses,
+        [
+            &DeletionStatus::Deleted,
+            &DeletionStatus::Error(String::from("cannot write the reference")),
+            &DeletionStatus::Deleted,
+        ]
+    );
+    assert_eq!(outcomes[1].archived, None);
+    assert_eq!(backend.branch_names(), ["main", "failing"]);
+
+    let archived: Vec<String> = broom
+        .archived_branches()
+        .unwrap()
+        .into_iter()
+        .map(|archived| archived.name)
+        .collect();
+
+    assert_eq!(archived, ["first", "last"]);
+
+    let journal = std::fs::read_to_string(git_dir.path().join("broom/journal")).unwrap();
+
+    assert_eq!(journal.lines().count(), 2);
+    assert!(!journal.contains("\"failing\""));
+}
+
+#[test]
+fn reads_protection_rules_from_the_git_directory() {
+    common::isolate();
+    let git_dir = TempDir::new().unwrap();
+    let backend = FakeBackend::new(&git_dir, "main")
+        .branch("main", &[])
+        .branch("release/1.0", &["main"]);
+
+    std::fs::write(
+        git_dir.path().join("config"),
+        "[broom]\n\tprotectedbranches = ^release/\n",
+    )
+    .unwrap();
+
+    let broom = GitBroom::builder()
+        .backend(backend.clone())
+        .branch("main")
+        .build();
+
+    let report = broom.scan().unwrap();
+    let outcomes = broom.apply(&report.merged).unwrap();
+
+    assert!(report.merged[0].protected);
+    assert_eq!(outcomes[0].status, DeletionStatus::Protected);
+    assert_eq!(backend.branch_names(), ["main", "release/1.0"]);
+}
+
+#[test]
+fn reports_unsupported_operations() {
+    common::isolate();
+    let git_dir = TempDir::new().unwrap();
+    let backend = FakeBackend::new(&git_dir, "main")
+        .branch("main", &[])
+        .branch("unmerged", &[]);
+
+    let result = GitBroom::builder()
+        .backend(backend)
+        .branch("main")
+        .squash_merged(true)
+        .build()
+        .scan();
+
+    assert!(matches!(result, Err(Error::Unsupported)));
+}
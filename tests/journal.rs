@@ -0,0 +1,117 @@
+/*
+Git Broom
+Copyright (C) 2024  All contributors.
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+mod common;
+
+use common::{TestRepo, BACKENDS};
+use git_broom::{Error, GitBroom};
+
+fn delete_merged(broom: &GitBroom) {
+    let report = broom.scan().unwrap();
+    broom.apply(&report.merged).unwrap();
+}
+
+#[test]
+fn restores_deleted_branches_with_their_upstream() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.merged_branch("merged");
+        repo.git(&["config", "branch.merged.remote", "origin"]);
+        repo.git(&["config", "branch.merged.merge", "refs/heads/merged"]);
+        let tip = repo.git(&["rev-parse", "merged"]);
+
+        let broom = repo.builder_with(backend).branch("main").build();
+        delete_merged(&broom);
+
+        assert!(!repo.has_branch("merged"), "{:?}", backend);
+
+        let entry = broom.restore("merged").unwrap();
+
+        assert_eq!(entry.tip, tip, "{:?}", backend);
+        assert_eq!(entry.targets, ["main"], "{:?}", backend);
+        assert_eq!(repo.git(&["rev-parse", "merged"]), tip, "{:?}", backend);
+        assert_eq!(
+            repo.config("branch.merged.remote").as_deref(),
+            Some("origin"),
+            "{:?}",
+            backend
+        );
+        assert_eq!(
+            repo.config("branch.merged.merge").as_deref(),
+            Some("refs/heads/merged"),
+            "{:?}",
+            backend
+        );
+        assert!(
+            matches!(broom.restore("merged"), Err(Error::NotInJournal { .. })),
+            "{:?}",
+            backend
+        );
+    }
+}
+
+#[test]
+fn undoes_the_last_run() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.merged_branch("first");
+        delete_merged(&repo.builder_with(backend).branch("main").build());
+
+        repo.merged_branch("second");
+        repo.merged_branch("third");
+        let broom = repo.builder_with(backend).branch("main").build();
+        delete_merged(&broom);
+
+        let names: Vec<String> = broom
+            .undo()
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect();
+
+        assert_eq!(names, ["second", "third"], "{:?}", backend);
+        assert!(repo.has_branch("second") && repo.has_branch("third"));
+        assert!(!repo.has_branch("first"), "{:?}", backend);
+
+        broom.undo().unwrap();
+
+        assert!(repo.has_branch("first"), "{:?}", backend);
+        assert!(
+            matches!(broom.undo(), Err(Error::NothingToUndo)),
+            "{:?}",
+            backend
+        );
+    }
+}
+
+#[test]
+fn does_not_overwrite_existing_branches() {
+    let repo = TestRepo::new();
+    repo.merged_branch("merged");
+    let broom = repo.builder().branch("main").build();
+    delete_merged(&broom);
+
+    repo.git(&["branch", "merged", "main~1"]);
+    let tip = repo.git(&["rev-parse", "merged"]);
+
+    assert!(matches!(
+        broom.restore("merged"),
+        Err(Error::BranchExists { .. })
+    ));
+    assert!(matches!(broom.undo(), Err(Error::BranchExists { .. })));
+    assert_eq!(repo.git(&["rev-parse", "merged"]), tip);
+}
@@ -0,0 +1,228 @@
+/*
+Git Broom
+Copyright (C) 2024  All contributors.
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+mod common;
+
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+use common::{isolated, TestRepo};
+
+/// Runs `git broom --show-protection` on the repository, with the given environment.
+fn show_protection(repo: &TestRepo, env: &[(&str, &Path)]) -> Output {
+    let mut command = isolated(Command::new(env!("CARGO_BIN_EXE_git-broom")));
+
+    for (name, value) in env {
+        command.env(name, value);
+    }
+
+    command
+        .arg("--show-protection")
+        .arg(repo.path())
+        .env("LANG", "en_US.UTF-8")
+        .env("NO_COLOR", "1")
+        .stdin(Stdio::null())
+        .output()
+        .expect("cannot run git-broom")
+}
+
+/// The patterns of the protection rules of the repository, along with the file each comes from.
+fn rules(repo: &TestRepo) -> Vec<(String, String, String)> {
+    repo.builder()
+        .build()
+        .protection_rules()
+        .unwrap()
+        .into_iter()
+        .map(|rule| (rule.key, rule.pattern.as_str().to_string(), rule.origin))
+        .collect()
+}
+
+fn rule(key: &str, pattern: &str, origin: &Path) -> (String, String, String) {
+    (
+        key.to_string(),
+        pattern.to_string(),
+        origin.display().to_string(),
+    )
+}
+
+#[test]
+fn combines_the_rules_of_included_files() {
+    let repo = TestRepo::new();
+    let included = repo.sibling("protection.config");
+    std::fs::write(
+        &included,
+        "[broom]\n\tprotectedbranches = ^release/\n\tprotectedremotebranches = ^origin/main$\n",
+    )
+    .unwrap();
+    repo.git(&["config", "broom.protectedbranches", "^hotfix/,^support/"]);
+    repo.git(&["config", "include.path", included.to_str().unwrap()]);
+
+    let local = repo.path().join(".git").join("config");
+
+    assert_eq!(
+        rules(&repo),
+        [
+            rule("broom.protectedbranches", "^hotfix/", &local),
+            rule("broom.protectedbranches", "^support/", &local),
+            rule("broom.protectedbranches", "^release/", &included),
+            rule("broom.protectedremotebranches", "^origin/main$", &included),
+        ]
+    );
+}
+
+#[test]
+fn follows_conditional_includes() {
+    let repo = TestRepo::new();
+    let matching = repo.sibling("matching.config");
+    let other = repo.sibling("other.config");
+    std::fs::write(&matching, "[broom]\n\tprotectedbranches = ^release/\n").unwrap();
+    std::fs::write(&other, "[broom]\n\tprotectedbranches = ^hotfix/\n").unwrap();
+    repo.git(&[
+        "config",
+        &format!("includeIf.gitdir:{}/.path", repo.path().display()),
+        matching.to_str().unwrap(),
+    ]);
+    repo.git(&[
+        "config",
+        "includeIf.gitdir:/elsewhere/.path",
+        other.to_str().unwrap(),
+    ]);
+    repo.git(&[
+        "config",
+        "includeIf.onbranch:main.path",
+        matching.to_str().unwrap(),
+    ]);
+    repo.merged_branch("release/1.0");
+    repo.merged_branch("hotfix/1.0");
+
+    let report = repo.builder().branch("main").build().scan().unwrap();
+    let protected: Vec<(&str, bool)> = report
+        .merged
+        .iter()
+        .map(|branch| (branch.name.as_str(), branch.protected))
+        .collect();
+
+    assert_eq!(protected, [("hotfix/1.0", false), ("release/1.0", true)]);
+    assert_eq!(
+        rules(&repo),
+        [
+            rule("broom.protectedbranches", "^release/", &matching),
+            rule("broom.protectedbranches", "^release/", &matching),
+        ]
+    );
+}
+
+#[test]
+fn reads_the_rules_of_the_repository_given_by_the_environment() {
+    let repo = TestRepo::new();
+    repo.git(&["config", "broom.protectedbranches", "^release/"]);
+
+    let output = isolated(Command::new(env!("CARGO_BIN_EXE_git-broom")))
+        .arg("--show-protection")
+        .current_dir(repo.sibling(""))
+        .env("GIT_DIR", repo.path().join(".git"))
+        .env("GIT_WORK_TREE", repo.path())
+        .env("LANG", "en_US.UTF-8")
+        .env("NO_COLOR", "1")
+        .stdin(Stdio::null())
+        .output()
+        .expect("cannot run git-broom");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout.contains("^release/"), "{}", stdout);
+}
+
+#[test]
+fn reads_the_global_file_along_with_the_local_one() {
+    let repo = TestRepo::new();
+    let global = repo.sibling("global.config");
+    std::fs::write(&global, "[broom]\n\tprotectedbranches = ^release/\n").unwrap();
+    repo.git(&["config", "broom.protectedbranches", "^hotfix/"]);
+
+    let output = show_protection(&repo, &[("GIT_CONFIG_GLOBAL", &global)]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(
+        stdout.contains("rules in broom.protectedbranches"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("^release/"), "{}", stdout);
+    assert!(stdout.contains(&global.display().to_string()), "{}", stdout);
+    assert!(stdout.contains("^hotfix/"), "{}", stdout);
+    assert!(
+        stdout.contains(
+            &repo
+                .path()
+                .join(".git")
+                .join("config")
+                .display()
+                .to_string()
+        ),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn reads_the_files_of_the_home_directory() {
+    let repo = TestRepo::new();
+    let home = repo.sibling("home");
+    let xdg = home.join(".config");
+    std::fs::create_dir_all(xdg.join("git")).unwrap();
+    std::fs::write(
+        home.join(".gitconfig"),
+        "[broom]\n\tprotectedbranches = ^release/\n",
+    )
+    .unwrap();
+    std::fs::write(
+        xdg.join("git").join("config"),
+        "[broom]\n\tprotectedbranches = ^hotfix/\n",
+    )
+    .unwrap();
+
+    let mut command = isolated(Command::new(env!("CARGO_BIN_EXE_git-broom")));
+    let output = command
+        .arg("--show-protection")
+        .arg(repo.path())
+        .env_remove("GIT_CONFIG_GLOBAL")
+        .env("HOME", &home)
+        .env("XDG_CONFIG_HOME", &xdg)
+        .env("LANG", "en_US.UTF-8")
+        .env("NO_COLOR", "1")
+        .stdin(Stdio::null())
+        .output()
+        .expect("cannot run git-broom");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout.contains("^release/"), "{}", stdout);
+    assert!(
+        stdout.contains(&home.join(".gitconfig").display().to_string()),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("^hotfix/"), "{}", stdout);
+    assert!(
+        stdout.contains(&xdg.join("git").join("config").display().to_string()),
+        "{}",
+        stdout
+    );
+}
@@ -0,0 +1,522 @@
+/*
+Git Broom
+Copyright (C) 2024  All contributors.
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+mod common;
+
+use std::path::Path;
+
+use common::{builder_from, BackendKind, TestRepo, BACKENDS};
+use git_broom::{Branch, MergeStatus, Notice};
+
+fn names(branches: &[Branch]) -> Vec<&str> {
+    branches.iter().map(|branch| branch.name.as_str()).collect()
+}
+
+#[test]
+fn finds_merged_branches() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.merged_branch("merged");
+        repo.branch("unmerged");
+
+        let report = repo
+            .builder_with(backend)
+            .branch("main")
+            .build()
+            .scan()
+            .unwrap();
+
+        assert_eq!(report.targets, ["main"], "{:?}", backend);
+        assert_eq!(names(&report.merged), ["merged"], "{:?}", backend);
+        assert_eq!(report.merged[0].status, MergeStatus::Merged);
+        assert_eq!(report.merged[0].targets, ["main"]);
+        assert!(!report.merged[0].protected);
+        assert_eq!(
+            report.merged[0].tip.as_deref(),
+            Some(repo.git(&["rev-parse", "merged"]).as_str())
+        );
+        assert!(report.gone.is_none());
+    }
+}
+
+#[test]
+fn finds_squash_merged_branches_when_asked_to() {
+    let repo = TestRepo::new();
+    repo.squash_merged_branch("squashed");
+    repo.branch("unmerged");
+
+    let report = repo.builder().branch("main").build().scan().unwrap();
+
+    assert!(report.merged.is_empty());
+
+    let broom = repo.builder().branch("main").squash_merged(true).build();
+    let report = broom.scan().unwrap();
+
+    assert_eq!(names(&report.merged), ["squashed"]);
+    assert_eq!(report.merged[0].status, MergeStatus::SquashMerged);
+    assert_eq!(report.merged[0].targets, ["main"]);
+
+    broom.apply(&report.merged).unwrap();
+
+    assert!(!repo.has_branch("squashed"));
+}
+
+#[test]
+fn finds_rebase_merged_branches_when_asked_to() {
+    let repo = TestRepo::new();
+    repo.rebase_merged_branch("rebased");
+    repo.branch("unmerged");
+
+    let report = repo.builder().branch("main").build().scan().unwrap();
+
+    assert!(report.merged.is_empty());
+
+    let report = repo
+        .builder()
+        .branch("main")
+        .rebase_merged(true)
+        .build()
+        .scan()
+        .unwrap();
+
+    assert_eq!(names(&report.merged), ["rebased"]);
+    assert_eq!(report.merged[0].status, MergeStatus::RebaseMerged(2));
+}
+
+#[test]
+fn checks_against_the_upstream_of_the_targets_when_asked_to() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.push("main");
+        repo.merged_branch("merged");
+        repo.push("main");
+        repo.git(&["reset", "--quiet", "--hard", "HEAD~1"]);
+
+        let report = repo
+            .builder_with(backend)
+            .branch("main")
+            .build()
+            .scan()
+            .unwrap();
+
+        assert!(report.merged.is_empty(), "{:?}", backend);
+        assert_eq!(
+            report.notices,
+            [Notice::BehindUpstream {
+                branch: String::from("main"),
+                upstream: String::from("origin/main"),
+                commits: 2,
+            }],
+            "{:?}",
+            backend
+        );
+
+        let report = repo
+            .builder_with(backend)
+            .branch("main")
+            .upstream(true)
+            .build()
+            .scan()
+            .unwrap();
+
+        assert_eq!(report.targets, ["origin/main"], "{:?}", backend);
+        assert_eq!(names(&report.merged), ["merged"], "{:?}", backend);
+    }
+}
+
+#[test]
+fn defaults_to_the_current_branch() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.merged_branch("merged");
+
+        let report = repo.builder_with(backend).build().scan().unwrap();
+
+        assert_eq!(report.targets, ["main"], "{:?}", backend);
+        assert_eq!(names(&report.merged), ["merged"], "{:?}", backend);
+    }
+}
+
+#[test]
+fn uses_the_default_branch_when_asked_to() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.push("main");
+        repo.git(&["remote", "set-head", "origin", "main"]);
+        repo.branch("feature");
+        repo.merged_branch("merged");
+        repo.git(&["checkout", "--quiet", "feature"]);
+
+        let report = repo.builder_with(backend).build().scan().unwrap();
+
+        assert_eq!(report.targets, ["feature"], "{:?}", backend);
+        assert!(report.merged.is_empty(), "{:?}", backend);
+
+        let report = repo
+            .builder_with(backend)
+            .default_target(true)
+            .build()
+            .scan()
+            .unwrap();
+
+        assert_eq!(report.targets, ["main"], "{:?}", backend);
+        assert_eq!(names(&report.merged), ["merged"], "{:?}", backend);
+        assert_eq!(
+            report.notices,
+            [Notice::DefaultBranch {
+                branch: String::from("main"),
+                source: String::from("refs/remotes/origin/HEAD"),
+            }],
+            "{:?}",
+            backend
+        );
+    }
+}
+
+#[test]
+fn falls_back_to_the_configured_default_branch() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.branch("feature");
+        repo.merged_branch("merged");
+        repo.git(&["checkout", "--quiet", "feature"]);
+        repo.git(&["config", "init.defaultBranch", "main"]);
+        repo.git(&["config", "broom.defaulttarget", "true"]);
+
+        let report = repo.builder_with(backend).build().scan().unwrap();
+
+        assert_eq!(report.targets, ["main"], "{:?}", backend);
+        assert_eq!(names(&report.merged), ["merged"], "{:?}", backend);
+        assert_eq!(
+            report.notices,
+            [Notice::DefaultBranch {
+                branch: String::from("main"),
+                source: String::from("init.defaultBranch"),
+            }],
+            "{:?}",
+            backend
+        );
+    }
+}
+
+/// Scans from a directory with `main` as the target, and returns the merged branches, telling the
+/// protected ones.
+fn scan_protection_from(backend: BackendKind, directory: &Path) -> Vec<(String, bool)> {
+    builder_from(backend, directory)
+        .branch("main")
+        .build()
+        .scan()
+        .unwrap()
+        .merged
+        .into_iter()
+        .map(|branch| (branch.name, branch.protected))
+        .collect()
+}
+
+fn protected_release() -> Vec<(String, bool)> {
+    vec![
+        (String::from("merged"), false),
+        (String::from("release/1.0"), true),
+    ]
+}
+
+#[test]
+fn scans_from_a_subdirectory() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.merged_branch("merged");
+        repo.merged_branch("release/1.0");
+        repo.git(&["config", "broom.protectedbranches", "^release/"]);
+        let subdirectory = repo.path().join("path").join("sub");
+        std::fs::create_dir_all(&subdirectory).unwrap();
+
+        assert_eq!(
+            scan_protection_from(backend, &subdirectory),
+            protected_release(),
+            "{:?}",
+            backend
+        );
+    }
+}
+
+#[test]
+fn scans_from_a_linked_worktree() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.merged_branch("merged");
+        repo.merged_branch("release/1.0");
+        repo.git(&["config", "broom.protectedbranches", "^release/"]);
+        repo.git(&["branch", "feature"]);
+        let worktree = repo.worktree("wt", "feature");
+
+        assert_eq!(
+            scan_protection_from(backend, &worktree),
+            protected_release(),
+            "{:?}",
+            backend
+        );
+    }
+}
+
+#[test]
+fn scans_a_bare_repository() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.merged_branch("merged");
+        repo.merged_branch("release/1.0");
+        let bare = repo.sibling("bare.git");
+        repo.git(&["clone", "--quiet", "--bare", ".", bare.to_str().unwrap()]);
+        repo.git(&[
+            "--git-dir",
+            bare.to_str().unwrap(),
+            "config",
+            "broom.protectedbranches",
+            "^release/",
+        ]);
+
+        assert_eq!(
+            scan_protection_from(backend, &bare),
+            protected_release(),
+            "{:?}",
+            backend
+        );
+    }
+}
+
+#[test]
+fn marks_protected_branches() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.merged_branch("merged");
+        repo.merged_branch("release/1.0");
+        repo.git(&["config", "broom.protectedbranches", "^release/"]);
+
+        let report = repo
+            .builder_with(backend)
+            .branch("main")
+            .build()
+            .scan()
+            .unwrap();
+
+        let protected: Vec<(&str, bool)> = report
+            .merged
+            .iter()
+            .map(|branch| (branch.name.as_str(), branch.protected))
+            .collect();
+
+        assert_eq!(
+            protected,
+            [("merged", false), ("release/1.0", true)],
+            "{:?}",
+            backend
+        );
+    }
+}
+
+#[test]
+fn finds_branches_checked_out_in_worktrees() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.merged_branch("merged");
+        let worktree = repo.worktree("merged-worktree", "merged");
+
+        let report = repo
+            .builder_with(backend)
+            .branch("main")
+            .build()
+            .scan()
+            .unwrap();
+
+        assert_eq!(names(&report.merged), ["merged"], "{:?}", backend);
+        assert_eq!(report.merged[0].worktree, Some(worktree), "{:?}", backend);
+    }
+}
+
+#[test]
+fn finds_branches_whose_upstream_is_gone() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.merged_branch("merged");
+        repo.gone_branch("gone");
+        repo.branch("unmerged");
+
+        let report = repo
+            .builder_with(backend)
+            .branch("main")
+            .gone(true)
+            .build()
+            .scan()
+            .unwrap();
+
+        let gone = report.gone.expect("gone branches were looked for");
+
+        assert_eq!(names(&report.merged), ["merged"], "{:?}", backend);
+        assert_eq!(names(&gone), ["gone"], "{:?}", backend);
+        assert_eq!(gone[0].status, MergeStatus::UpstreamGone);
+    }
+}
+
+#[test]
+fn fetches_and_prunes_the_remotes_when_asked_to() {
+    let repo = TestRepo::new();
+    repo.branch("gone");
+    repo.push("gone");
+    repo.git_origin(&["branch", "--quiet", "-D", "gone"]);
+
+    let report = repo
+        .builder()
+        .branch("main")
+        .gone(true)
+        .build()
+        .scan()
+        .unwrap();
+
+    assert!(report.gone.unwrap().is_empty());
+
+    let report = repo
+        .builder()
+        .branch("main")
+        .gone(true)
+        .fetch(true)
+        .build()
+        .scan()
+        .unwrap();
+
+    assert_eq!(names(&report.gone.unwrap()), ["gone"]);
+    assert_eq!(
+        report.notices,
+        [Notice::Fetched {
+            remote: String::from("origin"),
+            pruned: vec![String::from("origin/gone")],
+        }]
+    );
+    assert_eq!(repo.git(&["branch", "--remotes"]), "");
+}
+
+#[test]
+fn fails_without_a_target_branch() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+
+        let result = repo
+            .builder_with(backend)
+            .branch("^missing.*")
+            .build()
+            .scan();
+
+        assert!(
+            matches!(result, Err(git_broom::Error::NoTargetBranch)),
+            "{:?}",
+            backend
+        );
+    }
+}
+
+#[test]
+fn reports_every_target_branch_which_does_not_exist() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.branch("feature/develop-ui");
+        repo.git(&["config", "broom.targets", "main,develop,release"]);
+
+        match repo.builder_with(backend).build().scan() {
+            Err(git_broom::Error::NoSuchTarget { targets }) => {
+                assert_eq!(targets, ["develop", "release"], "{:?}", backend)
+            }
+            result => panic!("unexpected {:?} with {:?}", result, backend),
+        }
+    }
+}
+
+#[test]
+fn reads_every_value_of_the_target_configuration() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.branch("develop");
+        repo.branch("release");
+        repo.git(&["config", "--add", "broom.targets", "main"]);
+        repo.git(&["config", "--add", "broom.targets", "develop,release"]);
+
+        let report = repo.builder_with(backend).build().scan().unwrap();
+
+        assert_eq!(
+            report.targets,
+            ["main", "develop", "release"],
+            "{:?}",
+            backend
+        );
+    }
+}
+
+#[test]
+fn matches_target_patterns_against_whole_branch_names() {
+    for backend in BACKENDS {
+        let repo = TestRepo::new();
+        repo.branch("release/1.0");
+        repo.branch("prerelease/1.0");
+        repo.branch("release/1.0-wip");
+
+        let report = repo
+            .builder_with(backend)
+            .branch("^release/[0-9.]+")
+            .build()
+            .scan()
+            .unwrap();
+
+        assert_eq!(report.targets, ["release/1.0"], "{:?}", backend);
+    }
+}
+
+#[test]
+fn fails_on_an_invalid_target_pattern() {
+    let repo = TestRepo::new();
+
+    match repo.builder().branch("^(unclosed").build().scan() {
+        Err(git_broom::Error::InvalidTargetPattern { pattern, .. }) => {
+            assert_eq!(pattern, "^(unclosed")
+        }
+        result => panic!("unexpected {:?}", result),
+    }
+}
+
+#[test]
+fn fails_on_an_invalid_protection_rule() {
+    let repo = TestRepo::new();
+    repo.git(&["config", "broom.protectedbranches", "main,(unclosed"]);
+
+    match repo.builder().build().scan() {
+        Err(git_broom::Error::InvalidProtectionRule { pattern, key, .. }) => {
+            assert_eq!(pattern, "(unclosed");
+            assert_eq!(key, "broom.protectedbranches");
+        }
+        result => panic!("unexpected {:?}", result),
+    }
+}
+
+#[test]
+fn fails_outside_of_a_repository() {
+    common::isolate();
+    let directory = tempfile::tempdir().unwrap();
+
+    let result = git_broom::GitBroom::builder()
+        .repository(directory.path())
+        .build()
+        .scan();
+
+    assert!(matches!(result, Err(git_broom::Error::NotARepository)));
+}